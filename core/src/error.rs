@@ -0,0 +1,100 @@
+use serde::Serialize;
+
+/// Stable machine-readable category of an emulator error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Malformed params, BOCs or unsupported accounts and messages.
+    InvalidInput,
+    /// Invalid or incomplete blockchain config.
+    ConfigError,
+    /// Executor failed to produce a transaction.
+    ExecutorFatal,
+    /// External message was not accepted by the contract.
+    NotAccepted,
+    /// Unexpected emulator failure.
+    Internal,
+}
+
+impl ErrorKind {
+    /// Finds the first tagged error in the chain.
+    ///
+    /// Untagged errors are treated as [`ErrorKind::Internal`].
+    pub fn of(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(|e| e.downcast_ref::<KindedError>())
+            .map(|e| e.kind)
+            .unwrap_or(Self::Internal)
+    }
+}
+
+/// Error tagged with an [`ErrorKind`].
+///
+/// Formats exactly as the inner error.
+#[derive(Debug)]
+pub struct KindedError {
+    pub kind: ErrorKind,
+    pub inner: anyhow::Error,
+}
+
+impl std::fmt::Display for KindedError {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl std::error::Error for KindedError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+pub trait WithErrorKind<T> {
+    fn with_kind(self, kind: ErrorKind) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> WithErrorKind<T> for Result<T, E> {
+    fn with_kind(self, kind: ErrorKind) -> anyhow::Result<T> {
+        self.map_err(|e| {
+            anyhow::Error::new(KindedError {
+                kind,
+                inner: e.into(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn error_kind_survives_context() {
+        let res = Err::<(), _>(anyhow::anyhow!("inner"))
+            .context("Failed to unpack config params")
+            .with_kind(ErrorKind::ConfigError)
+            .context("outer");
+
+        let e = res.unwrap_err();
+        assert_eq!(ErrorKind::of(&e), ErrorKind::ConfigError);
+        assert_eq!(e.to_string(), "outer");
+        assert_eq!(
+            format!("{e:#}"),
+            "outer: Failed to unpack config params: inner"
+        );
+
+        let e = anyhow::anyhow!("Failed to commit transaction");
+        assert_eq!(ErrorKind::of(&e), ErrorKind::Internal);
+
+        // The outermost tag wins.
+        let res = Err::<(), _>(anyhow::anyhow!("inner"))
+            .with_kind(ErrorKind::InvalidInput)
+            .with_kind(ErrorKind::ConfigError);
+        assert_eq!(ErrorKind::of(&res.unwrap_err()), ErrorKind::ConfigError);
+    }
+}
@@ -0,0 +1,108 @@
+/// Symbolic name and description of a standard exit code.
+#[derive(Debug, Clone, Copy)]
+pub struct ExitCodeInfo {
+    pub code: i32,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl ExitCodeInfo {
+    /// Finds info for a standard compute phase exit code.
+    pub fn compute(code: i32) -> Option<&'static Self> {
+        COMPUTE_EXIT_CODES.iter().find(|info| info.code == code)
+    }
+
+    /// Finds info for a standard action phase result code.
+    pub fn action(code: i32) -> Option<&'static Self> {
+        ACTION_RESULT_CODES.iter().find(|info| info.code == code)
+    }
+}
+
+/// Returns a symbolic name of a standard compute phase exit code.
+pub fn compute_name(code: i32) -> Option<&'static str> {
+    ExitCodeInfo::compute(code).map(|info| info.name)
+}
+
+/// Returns a description of a standard compute phase exit code.
+pub fn compute_description(code: i32) -> Option<&'static str> {
+    ExitCodeInfo::compute(code).map(|info| info.description)
+}
+
+/// Returns a symbolic name of a standard action phase result code.
+pub fn action_name(code: i32) -> Option<&'static str> {
+    ExitCodeInfo::action(code).map(|info| info.name)
+}
+
+/// Returns a description of a standard action phase result code.
+pub fn action_description(code: i32) -> Option<&'static str> {
+    ExitCodeInfo::action(code).map(|info| info.description)
+}
+
+const fn info(code: i32, name: &'static str, description: &'static str) -> ExitCodeInfo {
+    ExitCodeInfo {
+        code,
+        name,
+        description,
+    }
+}
+
+/// Codes produced by the VM. Other codes are thrown by contracts.
+#[rustfmt::skip]
+static COMPUTE_EXIT_CODES: &[ExitCodeInfo] = &[
+    info(0, "ok", "Successful execution"),
+    info(1, "alt_ok", "Alternative successful execution"),
+    info(2, "stack_underflow", "Not enough values on the stack"),
+    info(3, "stack_overflow", "Too many values on the stack"),
+    info(4, "integer_overflow", "Integer overflow or division by zero"),
+    info(5, "range_check", "Integer is out of the expected range"),
+    info(6, "invalid_opcode", "Invalid or unknown instruction"),
+    info(7, "type_check", "Value has an unexpected type"),
+    info(8, "cell_overflow", "Cell builder overflow"),
+    info(9, "cell_underflow", "Not enough data or references in a cell slice"),
+    info(10, "dictionary_error", "Invalid dictionary"),
+    info(11, "unknown", "Unknown error"),
+    info(12, "fatal", "Fatal VM error"),
+    info(13, "out_of_gas", "Out of gas"),
+    info(-14, "out_of_gas", "Out of gas"),
+    info(14, "virtualization_error", "Pruned branch cell was accessed"),
+];
+
+#[rustfmt::skip]
+static ACTION_RESULT_CODES: &[ExitCodeInfo] = &[
+    info(0, "ok", "All actions were applied"),
+    info(32, "invalid_action_list", "Action list is invalid"),
+    info(33, "action_list_too_long", "Action list is too long"),
+    info(34, "invalid_action", "Action is invalid or not supported"),
+    info(35, "invalid_source_address", "Invalid source address in an outbound message"),
+    info(36, "invalid_destination_address", "Invalid destination address in an outbound message"),
+    info(37, "not_enough_balance", "Not enough balance to send a message"),
+    info(38, "not_enough_extra_currencies", "Not enough extra currencies to send a message"),
+    info(39, "message_too_large", "Outbound message does not fit into a cell after rewriting"),
+    info(40, "cannot_process_message", "Not enough funds to process a message"),
+    info(41, "library_not_found", "Library reference is null"),
+    info(42, "library_change_failed", "Failed to change a library"),
+    info(43, "library_limits_exceeded", "Library cells limit exceeded"),
+    info(50, "account_state_too_large", "Account state size limit exceeded"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separate_phase_codes() {
+        assert_eq!(compute_name(13), Some("out_of_gas"));
+        assert_eq!(compute_name(-14), Some("out_of_gas"));
+        assert_eq!(action_name(37), Some("not_enough_balance"));
+
+        // Codes thrown by contracts are not named after action phase codes.
+        assert_eq!(compute_name(37), None);
+        assert_eq!(compute_description(37), None);
+        // And vice versa.
+        assert_eq!(action_name(13), None);
+
+        assert_eq!(compute_name(0), Some("ok"));
+        assert_eq!(action_name(0), Some("ok"));
+        assert_eq!(compute_name(100), None);
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use tycho_types::cell::CellSliceRange;
+use tycho_types::prelude::*;
+use tycho_vm::{OwnedCellSlice, RcStackValue, SafeRc, Stack, StackValueType};
+
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::util::serde_string;
+
+/// Typed JSON representation of a stack value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonStackValue {
+    Null,
+    Nan,
+    Int {
+        #[serde(with = "serde_string")]
+        value: BigInt,
+    },
+    Cell {
+        #[serde(with = "Boc")]
+        value: Cell,
+    },
+    Slice {
+        #[serde(with = "Boc")]
+        value: Cell,
+        /// Visible part of the cell. The whole cell is used when `None`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        window: Option<SliceWindow>,
+    },
+    Builder {
+        #[serde(with = "Boc")]
+        value: Cell,
+    },
+    Tuple {
+        value: Vec<JsonStackValue>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SliceWindow {
+    pub bits_offset: u16,
+    pub refs_offset: u8,
+    pub bits: u16,
+    pub refs: u8,
+}
+
+impl JsonStackValue {
+    pub fn from_stack_value(value: &RcStackValue) -> Result<Self> {
+        Ok(match value.ty() {
+            StackValueType::Null => Self::Null,
+            StackValueType::Int => match value.as_int() {
+                Some(int) => Self::Int { value: int.clone() },
+                None => Self::Nan,
+            },
+            StackValueType::Cell => Self::Cell {
+                value: value.as_cell().context("Invalid cell value")?.clone(),
+            },
+            StackValueType::Slice => {
+                let slice = value.as_cell_slice().context("Invalid slice value")?;
+                let cell = slice.cell().clone();
+                let range = slice.range();
+
+                let window = (range != CellSliceRange::full(cell.as_ref())).then(|| SliceWindow {
+                    bits_offset: range.offset_bits(),
+                    refs_offset: range.offset_refs(),
+                    bits: range.size_bits(),
+                    refs: range.size_refs(),
+                });
+
+                Self::Slice {
+                    value: cell,
+                    window,
+                }
+            }
+            StackValueType::Builder => {
+                let builder = value.as_cell_builder().context("Invalid builder value")?;
+                Self::Builder {
+                    value: builder.clone().build()?,
+                }
+            }
+            StackValueType::Tuple => Self::Tuple {
+                value: value
+                    .as_tuple()
+                    .context("Invalid tuple value")?
+                    .iter()
+                    .map(Self::from_stack_value)
+                    .collect::<Result<_>>()?,
+            },
+            StackValueType::Cont => {
+                anyhow::bail!("Continuations are not supported in JSON stack")
+            }
+        })
+    }
+
+    pub fn into_stack_value(self) -> Result<RcStackValue> {
+        Ok(match self {
+            Self::Null => Stack::make_null(),
+            Self::Nan => Stack::make_nan(),
+            Self::Int { value } => SafeRc::new_dyn_value(value),
+            Self::Cell { value } => SafeRc::new_dyn_value(value),
+            Self::Slice { value, window } => {
+                let mut range = CellSliceRange::full(value.as_ref());
+                if let Some(window) = window {
+                    range
+                        .skip_first(window.bits_offset, window.refs_offset)
+                        .context("Slice window is out of bounds")?;
+                    range
+                        .only_first(window.bits, window.refs)
+                        .context("Slice window is out of bounds")?;
+                }
+                SafeRc::new_dyn_value(OwnedCellSlice::from((range, value)))
+            }
+            Self::Builder { value } => {
+                let mut builder = CellBuilder::new();
+                builder.store_slice(value.as_slice()?)?;
+                SafeRc::new_dyn_value(builder)
+            }
+            Self::Tuple { value } => SafeRc::new_dyn_value(
+                value
+                    .into_iter()
+                    .map(Self::into_stack_value)
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        })
+    }
+}
+
+/// Converts stack items into JSON values (from bottom to top).
+pub fn stack_to_json(stack: &Stack) -> Result<Vec<JsonStackValue>> {
+    stack
+        .items
+        .iter()
+        .map(JsonStackValue::from_stack_value)
+        .collect()
+}
+
+/// Builds a stack from JSON values (from bottom to top).
+pub fn stack_from_json(items: Vec<JsonStackValue>) -> Result<Stack> {
+    let items = items
+        .into_iter()
+        .map(JsonStackValue::into_stack_value)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Stack::with_items(items))
+}
+
+/// Parses either a JSON array of stack values or a base64 encoded stack BOC.
+pub fn parse_stack(stack: &str) -> Result<Stack> {
+    let stack = stack.trim();
+    (|| {
+        if stack.starts_with('[') {
+            let items = serde_json::from_str::<Vec<JsonStackValue>>(stack)
+                .context("Failed to parse JSON stack")?;
+            stack_from_json(items)
+        } else {
+            let cell = Boc::decode_base64(stack).context("Failed to deserialize stack cell")?;
+            cell.parse::<Stack>().context("Failed to deserialize stack")
+        }
+    })()
+    .with_kind(ErrorKind::InvalidInput)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_stack_roundtrip() {
+        let cell = CellBuilder::build_from((123u32, Cell::empty_cell())).unwrap();
+        let json = serde_json::json!([
+            { "type": "null" },
+            { "type": "nan" },
+            { "type": "int", "value": "-12345678901234567890" },
+            { "type": "cell", "value": Boc::encode_base64(&cell) },
+            { "type": "slice", "value": Boc::encode_base64(&cell) },
+            {
+                "type": "slice",
+                "value": Boc::encode_base64(&cell),
+                "window": { "bits_offset": 8, "refs_offset": 0, "bits": 16, "refs": 1 }
+            },
+            { "type": "builder", "value": Boc::encode_base64(&cell) },
+            { "type": "tuple", "value": [{ "type": "int", "value": "1" }, { "type": "null" }] },
+        ]);
+
+        let items = serde_json::from_value::<Vec<JsonStackValue>>(json.clone()).unwrap();
+        let stack = stack_from_json(items).unwrap();
+
+        // Must survive the BOC representation.
+        let stack = CellBuilder::build_from(&stack)
+            .unwrap()
+            .parse::<Stack>()
+            .unwrap();
+
+        let items = stack_to_json(&stack).unwrap();
+        assert_eq!(serde_json::to_value(items).unwrap(), json);
+    }
+}
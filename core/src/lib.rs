@@ -1,9 +1,17 @@
+pub mod error;
+pub mod exit_code;
+pub mod json_stack;
+pub mod local_shard;
 pub mod models;
+pub mod state_diff;
 pub mod subscriber;
 pub mod tvm_emulator;
 pub mod tx_emulator;
 pub mod util;
 
+#[cfg(test)]
+pub(crate) mod test_utils;
+
 #[cfg(feature = "native")]
 pub mod native;
 #[cfg(feature = "wasm")]
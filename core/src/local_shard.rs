@@ -0,0 +1,407 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use anyhow::{Context, Result};
+use tycho_types::models::{IntAddr, MsgInfo, OptionalAccount, ShardAccount, StdAddr};
+use tycho_types::prelude::*;
+
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::tx_emulator::{
+    EmulatedTransaction, EmulationInput, EmulationOutcome, EmulationParams, NotAcceptedMessage,
+    TxEmulator,
+};
+
+/// A set of accounts which route internal messages between each other.
+pub struct LocalShard {
+    accounts: HashMap<StdAddr, ShardAccount, ahash::RandomState>,
+    max_transactions: usize,
+}
+
+impl Default for LocalShard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalShard {
+    pub const DEFAULT_MAX_TRANSACTIONS: usize = 1000;
+
+    pub fn new() -> Self {
+        Self {
+            accounts: Default::default(),
+            max_transactions: Self::DEFAULT_MAX_TRANSACTIONS,
+        }
+    }
+
+    /// Limits the number of transactions produced by a single inbound message.
+    pub fn with_max_transactions(mut self, max_transactions: usize) -> Self {
+        self.max_transactions = max_transactions;
+        self
+    }
+
+    pub fn set_account(&mut self, address: StdAddr, account: ShardAccount) {
+        self.accounts.insert(address, account);
+    }
+
+    pub fn get_account(&self, address: &StdAddr) -> Option<&ShardAccount> {
+        self.accounts.get(address)
+    }
+
+    pub fn remove_account(&mut self, address: &StdAddr) -> Option<ShardAccount> {
+        self.accounts.remove(address)
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = (&StdAddr, &ShardAccount)> {
+        self.accounts.iter()
+    }
+
+    /// Executes an inbound message and all internal messages produced by it.
+    ///
+    /// Messages are delivered in the order of their `created_lt`. Accounts which
+    /// are not present in the shard are treated as `account_none`.
+    ///
+    /// Accounts are updated only when the whole tree is executed.
+    /// The shard is left untouched on error or if the message was not accepted.
+    pub fn execute(
+        &mut self,
+        emulator: &mut TxEmulator,
+        message: Cell,
+        params: &EmulationParams,
+    ) -> Result<LocalShardOutcome> {
+        let mut params = params.clone();
+
+        let mut updated = HashMap::<StdAddr, ShardAccount, ahash::RandomState>::default();
+        let mut transactions = Vec::<TransactionTreeNode>::new();
+        let mut queue = BinaryHeap::new();
+        let mut seqno = 0u64;
+        queue.push(Reverse(QueuedMessage {
+            created_lt: 0,
+            seqno,
+            parent: None,
+            message,
+        }));
+
+        while let Some(Reverse(item)) = queue.pop() {
+            anyhow::ensure!(
+                transactions.len() < self.max_transactions,
+                "Transaction tree is too big (limit: {})",
+                self.max_transactions
+            );
+
+            let address = match item.parent {
+                None => message_dst(&item.message).with_kind(ErrorKind::InvalidInput)?,
+                Some(_) => message_dst(&item.message)?,
+            };
+            let account = match updated
+                .get(&address)
+                .or_else(|| self.accounts.get(&address))
+            {
+                Some(account) => account.clone(),
+                None => empty_shard_account()?,
+            };
+
+            let input = EmulationInput::Ordinary(item.message);
+            let tx = match emulator.emulate(&account, input, &params)? {
+                EmulationOutcome::Committed(tx) => tx,
+                // NOTE: Only the root message can be external.
+                EmulationOutcome::NotAccepted(msg) => {
+                    return Ok(LocalShardOutcome::NotAccepted(msg));
+                }
+            };
+
+            let parsed = tx
+                .transaction
+                .load()
+                .context("Failed to unpack transaction")?;
+
+            // Move block lt past the last transaction to keep lt monotonic.
+            let end_lt = parsed.lt + 1 + parsed.out_msg_count.into_inner() as u64;
+            params.block_lt = params.block_lt.max(end_lt);
+
+            let index = transactions.len();
+            if let Some(parent) = item.parent {
+                transactions[parent].children.push(index);
+            }
+
+            for msg in parsed.out_msgs.values() {
+                let msg = msg.context("Invalid outbound messages dict")?;
+                let info = msg
+                    .parse::<MsgInfo>()
+                    .context("Failed to unpack outbound message info")?;
+
+                if let MsgInfo::Int(info) = info {
+                    seqno += 1;
+                    queue.push(Reverse(QueuedMessage {
+                        created_lt: info.created_lt,
+                        seqno,
+                        parent: Some(index),
+                        message: msg,
+                    }));
+                }
+            }
+
+            updated.insert(address.clone(), tx.shard_account.clone());
+
+            transactions.push(TransactionTreeNode {
+                address,
+                parent: item.parent,
+                children: Vec::new(),
+                tx,
+            });
+        }
+
+        self.accounts.extend(updated);
+
+        Ok(LocalShardOutcome::Committed(TransactionTree {
+            transactions,
+        }))
+    }
+}
+
+pub enum LocalShardOutcome {
+    /// All messages were delivered.
+    Committed(TransactionTree),
+    /// Inbound external message was not accepted by the contract.
+    NotAccepted(NotAcceptedMessage),
+}
+
+/// Transactions in the order of execution. The first one is the root.
+#[derive(Default)]
+pub struct TransactionTree {
+    pub transactions: Vec<TransactionTreeNode>,
+}
+
+impl TransactionTree {
+    pub fn root(&self) -> Option<&TransactionTreeNode> {
+        self.transactions.first()
+    }
+}
+
+pub struct TransactionTreeNode {
+    pub address: StdAddr,
+    /// Index of the transaction which produced the inbound message.
+    pub parent: Option<usize>,
+    /// Indices of the transactions produced by outbound messages.
+    pub children: Vec<usize>,
+    pub tx: EmulatedTransaction,
+}
+
+struct QueuedMessage {
+    created_lt: u64,
+    seqno: u64,
+    parent: Option<usize>,
+    message: Cell,
+}
+
+impl QueuedMessage {
+    fn key(&self) -> (u64, u64) {
+        (self.created_lt, self.seqno)
+    }
+}
+
+impl Eq for QueuedMessage {}
+
+impl PartialEq for QueuedMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Ord for QueuedMessage {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for QueuedMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn message_dst(message: &Cell) -> Result<StdAddr> {
+    let dst = match message
+        .parse::<MsgInfo>()
+        .context("Failed to unpack message info")?
+    {
+        MsgInfo::Int(info) => info.dst,
+        MsgInfo::ExtIn(info) => info.dst,
+        MsgInfo::ExtOut(_) => {
+            anyhow::bail!("Only internal and external inbound messages are accepted")
+        }
+    };
+
+    match dst {
+        IntAddr::Std(addr) => Ok(addr),
+        IntAddr::Var(_) => anyhow::bail!("var_addr is not supported"),
+    }
+}
+
+pub(crate) fn empty_shard_account() -> Result<ShardAccount> {
+    Ok(ShardAccount {
+        account: Lazy::new(&OptionalAccount::EMPTY)?,
+        last_trans_hash: HashBytes::ZERO,
+        last_trans_lt: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tycho_types::models::{ExtInMsgInfo, IntMsgInfo, OwnedMessage};
+    use tycho_types::num::Tokens;
+
+    use super::*;
+    use crate::test_utils::{
+        SEND_ONE, THROW, addr, int_msg, make_account, make_emulator, make_params,
+    };
+
+    // DROP (selector), LDREF, LDREF, DROP, SWAP, (PUSHINT 1, SENDRAWMSG) x2
+    const SEND_TWO: &[u8] = &[
+        0x30, 0xd4, 0xd4, 0x30, 0x01, 0x71, 0xfb, 0x00, 0x71, 0xfb, 0x00,
+    ];
+
+    fn in_msg_info(node: &TransactionTreeNode) -> IntMsgInfo {
+        let tx = node.tx.transaction.load().unwrap();
+        match tx.in_msg.unwrap().parse::<MsgInfo>().unwrap() {
+            MsgInfo::Int(info) => info,
+            _ => panic!("internal message expected"),
+        }
+    }
+
+    fn committed(outcome: LocalShardOutcome) -> TransactionTree {
+        match outcome {
+            LocalShardOutcome::Committed(tree) => tree,
+            LocalShardOutcome::NotAccepted(_) => panic!("message must be accepted"),
+        }
+    }
+
+    #[test]
+    fn route_internal_message() {
+        let (user, a, b) = (addr(0xff), addr(1), addr(2));
+
+        let mut shard = LocalShard::new();
+        shard.set_account(a.clone(), make_account(&a, SEND_ONE));
+        shard.set_account(b.clone(), make_account(&b, &[]));
+
+        let msg = int_msg(&user, &a, false, &[int_msg(&a, &b, false, &[])]);
+        let tree = committed(
+            shard
+                .execute(&mut make_emulator(), msg, &make_params())
+                .unwrap(),
+        );
+
+        let [root, child] = tree.transactions.as_slice() else {
+            panic!("two transactions expected");
+        };
+        assert_eq!(root.address, a);
+        assert_eq!(root.children, [1]);
+        assert_eq!(child.address, b);
+        assert_eq!(child.parent, Some(0));
+        assert_eq!(in_msg_info(child).src, IntAddr::Std(a.clone()));
+
+        for node in &tree.transactions {
+            let account = shard.get_account(&node.address).unwrap();
+            assert_eq!(account.last_trans_lt, node.tx.shard_account.last_trans_lt);
+        }
+    }
+
+    #[test]
+    fn bounce_to_sender() {
+        let (user, a, b) = (addr(0xff), addr(1), addr(2));
+
+        let mut shard = LocalShard::new();
+        shard.set_account(a.clone(), make_account(&a, SEND_ONE));
+        shard.set_account(b.clone(), make_account(&b, THROW));
+
+        let msg = int_msg(&user, &a, false, &[int_msg(&a, &b, true, &[])]);
+        let tree = committed(
+            shard
+                .execute(&mut make_emulator(), msg, &make_params())
+                .unwrap(),
+        );
+
+        let addresses = tree.transactions.iter().map(|node| &node.address);
+        assert!(addresses.eq([&a, &b, &a]));
+
+        let bounced = &tree.transactions[2];
+        assert_eq!(bounced.parent, Some(1));
+        assert!(in_msg_info(bounced).bounced);
+    }
+
+    #[test]
+    fn deliver_in_created_lt_order() {
+        let (user, a, b, c) = (addr(0xff), addr(1), addr(2), addr(3));
+
+        let mut shard = LocalShard::new();
+        shard.set_account(a.clone(), make_account(&a, SEND_TWO));
+        shard.set_account(b.clone(), make_account(&b, &[]));
+        shard.set_account(c.clone(), make_account(&c, &[]));
+
+        let to_b = int_msg(&a, &b, false, &[]);
+        let to_c = int_msg(&a, &c, false, &[]);
+        let msg = int_msg(&user, &a, false, &[to_b, to_c]);
+        let tree = committed(
+            shard
+                .execute(&mut make_emulator(), msg, &make_params())
+                .unwrap(),
+        );
+
+        let addresses = tree.transactions.iter().map(|node| &node.address);
+        assert!(addresses.eq([&a, &b, &c]));
+        assert_eq!(tree.transactions[0].children, [1, 2]);
+
+        let created_lt = tree.transactions[1..]
+            .iter()
+            .map(|node| in_msg_info(node).created_lt)
+            .collect::<Vec<_>>();
+        assert!(created_lt.is_sorted());
+
+        let tx_lt = tree
+            .transactions
+            .iter()
+            .map(|node| node.tx.transaction.load().unwrap().lt)
+            .collect::<Vec<_>>();
+        assert!(tx_lt.is_sorted());
+    }
+
+    #[test]
+    fn transactions_limit_keeps_state() {
+        let (user, a, b) = (addr(0xff), addr(1), addr(2));
+
+        let mut shard = LocalShard::new().with_max_transactions(1);
+        shard.set_account(a.clone(), make_account(&a, SEND_ONE));
+
+        let msg = int_msg(&user, &a, false, &[int_msg(&a, &b, false, &[])]);
+        let res = shard.execute(&mut make_emulator(), msg, &make_params());
+        assert!(res.is_err());
+
+        assert_eq!(shard.get_account(&a).unwrap().last_trans_lt, 0);
+        assert!(shard.get_account(&b).is_none());
+    }
+
+    #[test]
+    fn external_not_accepted() {
+        let a = addr(1);
+
+        let mut shard = LocalShard::new();
+        shard.set_account(a.clone(), make_account(&a, &[]));
+
+        let msg = CellBuilder::build_from(OwnedMessage {
+            info: MsgInfo::ExtIn(ExtInMsgInfo {
+                src: None,
+                dst: a.clone().into(),
+                import_fee: Tokens::ZERO,
+            }),
+            init: None,
+            body: Cell::empty_cell().into(),
+            layout: None,
+        })
+        .unwrap();
+
+        let outcome = shard
+            .execute(&mut make_emulator(), msg, &make_params())
+            .unwrap();
+        assert!(matches!(outcome, LocalShardOutcome::NotAccepted(_)));
+        assert_eq!(shard.get_account(&a).unwrap().last_trans_lt, 0);
+    }
+}
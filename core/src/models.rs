@@ -1,10 +1,27 @@
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use tycho_types::models::{ExtraCurrencyCollection, ShardAccount, StdAddr};
+use tycho_types::models::{
+    AccountState, AccountStatusChange, ActionPhase, BouncePhase, ComputePhase,
+    ComputePhaseSkipReason, CreditPhase, CurrencyCollection, ExtraCurrencyCollection, IntMsgInfo,
+    MsgInfo, OwnedMessage, ShardAccount, StateInit, StdAddr, StoragePhase, TickTock, Transaction,
+    TxInfo,
+};
+use tycho_types::num::Tokens;
 use tycho_types::prelude::*;
-use tycho_vm::{SafeRc, Stack};
+use tycho_vm::{SafeRc, Stack, StackValue, Tuple, VmLogMask};
 
-use crate::subscriber::VmLogRows;
-use crate::util::{JsonBool, serde_extra_currencies, serde_string, serde_ton_address};
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::exit_code;
+use crate::json_stack::{JsonStackValue, parse_stack, stack_from_json, stack_to_json};
+use crate::local_shard::LocalShardOutcome;
+use crate::state_diff::AccountStateDiff;
+use crate::subscriber::{LogLimits, TraceMode, VmLogDropped, VmLogRows, VmTraceStep};
+use crate::tvm_emulator::{Answer, CallOverrides, GetMethodOutcome, TvmEmulator};
+use crate::tx_emulator::{EmulationInput, EmulationJob, EmulationOutcome, TransactionFees};
+use crate::util::{
+    JsonBool, make_shard_account, resolve_vm_log_mask, serde_extra_currencies,
+    serde_optional_string, serde_string, serde_ton_address,
+};
 
 #[cfg(feature = "wasm")]
 #[wasm_bindgen::prelude::wasm_bindgen(typescript_custom_section)]
@@ -27,44 +44,176 @@ export type EmulatorParams = {
   full_body_in_bounced?: boolean;
   strict_extra_currency?: boolean;
   authority_marks_enabled?: boolean;
+  data_cells_diff?: boolean;
+  return_c7?: boolean;
+  trace?: TraceMode;
+  log_limits?: LogLimits;
+  vm_log_verbosity?: number;
+  /**
+   * VM log flags, takes precedence over `vm_log_verbosity`:
+   * 1 - messages, 2 - stack, 4 - code cell hash and offset,
+   * 8 - gas remaining, 16 - verbose stack, 32 - c5.
+   */
+  vm_log_mask?: number;
   prev_blocks_info?: string;
 };
 
-export type RunGetMethodParams = {
+export type EmulationJob = {
+  shard_account: string;
+  message?: string;
+  is_tock?: boolean;
+};
+
+export type ShardAccountParams = {
+  address: string;
+  balance: string;
+  extra_currencies?: { [k: string]: string };
+  state?: "active" | "uninit" | "frozen";
+  code?: string;
+  data?: string;
+  last_paid?: number;
+  last_trans_lt?: string;
+};
+
+export type RunGetMethodParams = AccountGetMethodParams & {
   code: string;
   data: string;
+  address: string;
+  balance: string;
+  extra_currencies?: { [k: string]: string };
+};
+
+export type AccountGetMethodParams = {
   verbosity: number;
   libs?: string;
   prev_blocks_info?: string;
-  address: string;
   unixtime: number;
-  balance: string;
-  extra_currencies?: { [k: string]: string };
   rand_seed: string;
   gas_limit: string;
-  method_id: number;
+  method_id?: number;
+  method?: number | string;
+  stack_format?: "boc" | "json";
+  return_c7?: boolean;
+  global_version?: number;
+  trace?: TraceMode;
+  log_limits?: LogLimits;
   debug_enabled: boolean;
 };
 
+export type GetMethodCall = {
+  method: number | string;
+  stack?: string | StackEntry[];
+  gas_limit?: string;
+  stack_format?: "boc" | "json";
+  log_limits?: LogLimits;
+};
+
+export type GetMethodCallResponse =
+  | RunGetMethodResponse
+  | {
+      success: false;
+      error: string;
+      error_kind: ErrorKind;
+      method_id: number;
+    };
+
+export type StackEntry =
+  | { type: "null" }
+  | { type: "nan" }
+  | { type: "int"; value: string }
+  | { type: "cell"; value: string }
+  | {
+      type: "slice";
+      value: string;
+      window?: { bits_offset: number; refs_offset: number; bits: number; refs: number };
+    }
+  | { type: "builder"; value: string }
+  | { type: "tuple"; value: StackEntry[] };
+
 export type OkResponse<T> = {
     ok: true;
     output: T;
     logs: string;
 };
 
+export type ErrorKind =
+  | "invalid_input"
+  | "config_error"
+  | "executor_fatal"
+  | "not_accepted"
+  | "internal";
+
 export type ErrResponse = {
     ok: false;
     message: string;
+    error_kind: ErrorKind;
+};
+
+export type RunGetMethodErrResponse = ErrResponse & {
+    method_id: number | null;
 };
 
 export type RunGetMethodResponse = {
     success: true;
-    stack: string;
+    method_id: number;
+    stack: string | StackEntry[];
     gas_used: string;
     debug_log: string;
     vm_exit_code: number;
+    vm_exit_code_name: string | null;
+    vm_exit_code_description: string | null;
     vm_log: string;
+    vm_log_dropped: VmLogDropped;
     missing_library: string | null;
+    vm_version: number;
+    c7?: C7Info;
+    trace?: VmTraceStep[];
+};
+
+export type TvmEmulatorSendMessageResponse = {
+    success: true;
+    gas_used: string;
+    vm_exit_code: number;
+    vm_exit_code_name: string | null;
+    vm_exit_code_description: string | null;
+    accepted: boolean;
+    vm_log: string;
+    vm_log_dropped: VmLogDropped;
+    missing_library: string | null;
+    actions: string | null;
+    new_code: string;
+    new_data: string;
+    vm_version: number;
+    c7?: C7Info;
+    trace?: VmTraceStep[];
+};
+
+export type C7Info = {
+  boc: string;
+  json: StackEntry;
+};
+
+export type TraceMode = "disabled" | "enabled" | "with_stack";
+
+export type LogLimits = {
+  max_rows?: number;
+  max_bytes?: number;
+  retention?: "head" | "tail" | "head_tail";
+};
+
+export type VmLogDropped = {
+  rows: number;
+  bytes: number;
+  trace_steps: number;
+};
+
+export type VmTraceStep = {
+  opcode: string;
+  code_hash: string | null;
+  offset: number | null;
+  gas_remaining: number | null;
+  gas_consumed: number | null;
+  stack?: string;
 };
 
 export type EmulatorResponse = EmulatorSuccess | EmulatorError;
@@ -72,20 +221,215 @@ export type EmulatorResponse = EmulatorSuccess | EmulatorError;
 export type EmulatorSuccess = {
   success: true;
   transaction: string;
+  description: TxDescription;
+  out_messages: OutMessage[];
+  fees: TxFees;
+  state_diff: AccountStateDiff;
   shard_account: string;
   debug_log: string;
   vm_log: string;
+  vm_log_dropped: VmLogDropped;
   actions: string | null;
+  c7?: C7Info;
+  trace?: VmTraceStep[];
+};
+
+export type LocalShardResponse =
+  | LocalShardSuccess
+  | LocalShardNotAccepted
+  | EmulatorError;
+
+export type LocalShardNotAccepted = {
+  success: false;
+  error: string;
+  error_kind: "not_accepted";
+  external_not_accepted: true;
+  debug_log: string;
+  vm_log: string;
+  vm_log_dropped: VmLogDropped;
+  vm_exit_code: number;
+  vm_exit_code_name: string | null;
+  vm_exit_code_description: string | null;
+  c7?: C7Info;
+  trace?: VmTraceStep[];
+};
+
+export type LocalShardSuccess = {
+  success: true;
+  transactions: LocalShardTransaction[];
+};
+
+export type LocalShardTransaction = {
+  address: string;
+  parent: number | null;
+  children: number[];
+  transaction: string;
+  description: TxDescription;
+  out_messages: OutMessage[];
+  fees: TxFees;
+  state_diff: AccountStateDiff;
+  shard_account: string;
+  debug_log: string;
+  vm_log: string;
+  vm_log_dropped: VmLogDropped;
+  actions: string | null;
+  c7?: C7Info;
+  trace?: VmTraceStep[];
+};
+
+export type AccountStatusName = "nonexist" | "uninit" | "active" | "frozen";
+
+export type AccountStorageStat = {
+  cells: string;
+  bits: string;
+  last_paid: number;
+  due_payment: string | null;
+};
+
+export type AccountStateDiff = {
+  status_before: AccountStatusName;
+  status_after: AccountStatusName;
+  balance_before: string;
+  balance_after: string;
+  balance_delta: string;
+  extra_currencies_delta: { [k: string]: string };
+  code_hash_before: string | null;
+  code_hash_after: string | null;
+  code_changed: boolean;
+  data_hash_before: string | null;
+  data_hash_after: string | null;
+  data_changed: boolean;
+  storage_before: AccountStorageStat | null;
+  storage_after: AccountStorageStat | null;
+  data_cells?: {
+    added: string[];
+    removed: string[];
+  };
+};
+
+export type TxFees = {
+  storage_fees_collected: string;
+  storage_fees_due: string;
+  gas_fees: string;
+  action_fees: string;
+  total_fwd_fees: string;
+  bounce_fees: string;
+  total_fees: string;
+  out_msgs: {
+    hash: string;
+    fwd_fee: string;
+    action_fee: string;
+    remaining_fwd_fee: string;
+  }[];
+};
+
+export type OutMessage = {
+  boc: string;
+  hash: string;
+  type: "int" | "ext_out";
+  src: string;
+  dst: string | null;
+  value: string | null;
+  value_extra: { [k: string]: string };
+  bounce: boolean | null;
+  bounced: boolean | null;
+  fwd_fee: string | null;
+  created_lt: string;
+  created_at: number;
+};
+
+export type AccountStatusChange = "unchanged" | "frozen" | "deleted";
+
+export type TxDescription = {
+  kind: "ordinary" | "tick" | "tock";
+  credit_first: boolean;
+  aborted: boolean;
+  destroyed: boolean;
+  total_fees: string;
+  storage_phase: {
+    storage_fees_collected: string;
+    storage_fees_due: string | null;
+    status_change: AccountStatusChange;
+  } | null;
+  credit_phase: {
+    due_fees_collected: string | null;
+    credit: string;
+    credit_extra: { [k: string]: string };
+  } | null;
+  compute_phase:
+    | {
+        type: "skipped";
+        reason: "no_state" | "bad_state" | "no_gas" | "suspended";
+      }
+    | {
+        type: "executed";
+        success: boolean;
+        msg_state_used: boolean;
+        account_activated: boolean;
+        gas_fees: string;
+        gas_used: string;
+        gas_limit: string;
+        gas_credit: string | null;
+        mode: number;
+        exit_code: number;
+        exit_code_name: string | null;
+        exit_code_description: string | null;
+        exit_arg: number | null;
+        vm_steps: number;
+        vm_init_state_hash: string;
+        vm_final_state_hash: string;
+      };
+  action_phase: {
+    success: boolean;
+    valid: boolean;
+    no_funds: boolean;
+    status_change: AccountStatusChange;
+    total_fwd_fees: string | null;
+    total_action_fees: string | null;
+    result_code: number;
+    result_code_name: string | null;
+    result_code_description: string | null;
+    result_arg: number | null;
+    total_actions: number;
+    special_actions: number;
+    skipped_actions: number;
+    messages_created: number;
+    action_list_hash: string;
+    total_message_cells: string;
+    total_message_bits: string;
+  } | null;
+  bounce_phase:
+    | { type: "negative_funds" }
+    | {
+        type: "no_funds";
+        msg_cells: string;
+        msg_bits: string;
+        req_fwd_fees: string;
+      }
+    | {
+        type: "executed";
+        msg_cells: string;
+        msg_bits: string;
+        msg_fees: string;
+        fwd_fees: string;
+      }
+    | null;
 };
 
 export type EmulatorError = {
   success: false;
   error: string;
+  error_kind: ErrorKind;
   debug_log: string;
 } & (
   | {
       vm_log: string;
+      vm_log_dropped: VmLogDropped;
       vm_exit_code: number;
+      vm_exit_code_name: string | null;
+      vm_exit_code_description: string | null;
+      c7?: C7Info;
+      trace?: VmTraceStep[];
     }
   | {}
 );
@@ -116,35 +460,366 @@ pub struct EmulatorParams {
     pub strict_extra_currency: Option<bool>,
     #[serde(default)]
     pub authority_marks_enabled: Option<bool>,
+    #[serde(default)]
+    pub data_cells_diff: bool,
+    #[serde(default)]
+    pub return_c7: bool,
+    #[serde(default)]
+    pub trace: TraceMode,
+    #[serde(default)]
+    pub log_limits: Option<LogLimits>,
+    /// Overrides VM log verbosity of the emulator.
+    #[serde(default)]
+    pub vm_log_verbosity: Option<i32>,
+    /// Overrides VM log mask of the emulator. Takes precedence over `vm_log_verbosity`.
+    #[serde(default)]
+    pub vm_log_mask: Option<u32>,
     #[serde(default, with = "Boc")]
     pub prev_blocks_info: Option<Cell>,
 }
 
+impl EmulatorParams {
+    pub fn vm_log_mask(&self) -> Option<VmLogMask> {
+        resolve_vm_log_mask(self.vm_log_mask, self.vm_log_verbosity)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EmulationJobParams {
+    #[serde(with = "BocRepr")]
+    pub shard_account: ShardAccount,
+    /// Inbound message. Tick-tock transaction is emulated when `None`.
+    #[serde(default, with = "Boc")]
+    pub message: Option<Cell>,
+    #[serde(default)]
+    pub is_tock: bool,
+}
+
+impl From<EmulationJobParams> for EmulationJob {
+    fn from(value: EmulationJobParams) -> Self {
+        let input = match value.message {
+            Some(msg) => EmulationInput::Ordinary(msg),
+            None if value.is_tock => EmulationInput::TickTock(TickTock::Tock),
+            None => EmulationInput::TickTock(TickTock::Tick),
+        };
+
+        Self {
+            account: value.shard_account,
+            input,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ShardAccountParams {
+    #[serde(with = "serde_ton_address")]
+    pub address: StdAddr,
+    #[serde(with = "serde_string")]
+    pub balance: u128,
+    #[serde(default, with = "serde_extra_currencies")]
+    pub extra_currencies: ExtraCurrencyCollection,
+    #[serde(default)]
+    pub state: ShardAccountState,
+    #[serde(default, with = "Boc")]
+    pub code: Option<Cell>,
+    #[serde(default, with = "Boc")]
+    pub data: Option<Cell>,
+    #[serde(default)]
+    pub last_paid: u32,
+    #[serde(default, with = "serde_string")]
+    pub last_trans_lt: u64,
+}
+
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShardAccountState {
+    #[default]
+    Active,
+    Uninit,
+    Frozen,
+}
+
+impl ShardAccountParams {
+    pub fn build(self) -> anyhow::Result<ShardAccount> {
+        let state = match self.state {
+            ShardAccountState::Uninit => {
+                anyhow::ensure!(
+                    self.code.is_none() && self.data.is_none(),
+                    "uninit account can't have code or data"
+                );
+                AccountState::Uninit
+            }
+            state => {
+                let state_init = StateInit {
+                    split_depth: None,
+                    special: None,
+                    code: self.code,
+                    data: self.data,
+                    libraries: Dict::new(),
+                };
+                if state == ShardAccountState::Frozen {
+                    let hash = *CellBuilder::build_from(&state_init)?.repr_hash();
+                    AccountState::Frozen(hash)
+                } else {
+                    AccountState::Active(state_init)
+                }
+            }
+        };
+
+        let balance = CurrencyCollection {
+            tokens: Tokens::new(self.balance),
+            other: self.extra_currencies,
+        };
+
+        make_shard_account(
+            self.address,
+            balance,
+            state,
+            self.last_paid,
+            self.last_trans_lt,
+        )
+    }
+}
+
+/// Internal message description for `TvmEmulator`.
+///
+/// Destination is always the emulated account.
+#[derive(Deserialize)]
+pub struct InternalMessageParams {
+    #[serde(default = "default_int_msg_src", with = "serde_ton_address")]
+    pub src: StdAddr,
+    #[serde(default = "default_true")]
+    pub bounce: bool,
+    #[serde(default)]
+    pub bounced: bool,
+    #[serde(with = "serde_string")]
+    pub value: u128,
+    #[serde(default, with = "serde_extra_currencies")]
+    pub extra_currencies: ExtraCurrencyCollection,
+    #[serde(default, with = "serde_string")]
+    pub fwd_fee: u128,
+    #[serde(default, with = "serde_string")]
+    pub created_lt: u64,
+    #[serde(default)]
+    pub created_at: u32,
+    #[serde(default, with = "Boc")]
+    pub state_init: Option<Cell>,
+    #[serde(default, with = "Boc")]
+    pub body: Option<Cell>,
+}
+
+impl InternalMessageParams {
+    pub fn build(self, dst: StdAddr) -> anyhow::Result<Cell> {
+        let init = match self.state_init {
+            Some(cell) => Some(
+                cell.parse::<StateInit>()
+                    .context("Failed to unpack state init")
+                    .with_kind(ErrorKind::InvalidInput)?,
+            ),
+            None => None,
+        };
+
+        let message = OwnedMessage {
+            info: MsgInfo::Int(IntMsgInfo {
+                ihr_disabled: true,
+                bounce: self.bounce,
+                bounced: self.bounced,
+                src: self.src.into(),
+                dst: dst.into(),
+                value: CurrencyCollection {
+                    tokens: Tokens::new(self.value),
+                    other: self.extra_currencies,
+                },
+                extra_flags: Default::default(),
+                fwd_fee: Tokens::new(self.fwd_fee),
+                created_lt: self.created_lt,
+                created_at: self.created_at,
+            }),
+            init,
+            body: self.body.unwrap_or_else(Cell::empty_cell).into(),
+            layout: None,
+        };
+
+        CellBuilder::build_from(message).map_err(Into::into)
+    }
+}
+
+fn default_int_msg_src() -> StdAddr {
+    StdAddr::new(-1, HashBytes::ZERO)
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Get-method params with an explicit contract state.
 #[derive(Deserialize)]
 pub struct RunGetMethodParams {
     #[serde(with = "Boc")]
     pub code: Cell,
     #[serde(with = "Boc")]
     pub data: Cell,
-    pub verbosity: i32,
-    #[serde(default, with = "Boc")]
-    pub libs: Option<Cell>,
-    #[serde(default, with = "Boc")]
-    pub prev_blocks_info: Option<Cell>,
     #[serde(with = "serde_ton_address")]
     pub address: StdAddr,
-    pub unixtime: u32,
     #[serde(with = "serde_string")]
     pub balance: u64,
     #[serde(default, with = "serde_extra_currencies")]
     pub extra_currencies: ExtraCurrencyCollection,
+    #[serde(flatten)]
+    pub base: AccountGetMethodParams,
+}
+
+/// Get-method params for a run against a `ShardAccount`.
+///
+/// Code, data, address and balance are taken from the account.
+#[derive(Deserialize)]
+pub struct AccountGetMethodParams {
+    pub verbosity: i32,
+    #[serde(default, with = "Boc")]
+    pub libs: Option<Cell>,
+    #[serde(default, with = "Boc")]
+    pub prev_blocks_info: Option<Cell>,
+    pub unixtime: u32,
     pub rand_seed: HashBytes,
     #[serde(with = "serde_string")]
     pub gas_limit: u64,
-    pub method_id: i32,
+    #[serde(default)]
+    pub method_id: Option<i32>,
+    /// Method id or name. Takes precedence over `method_id`.
+    #[serde(default)]
+    pub method: Option<MethodId>,
+    /// Format of the resulting stack.
+    #[serde(default)]
+    pub stack_format: StackFormat,
+    #[serde(default)]
+    pub return_c7: bool,
+    /// Overrides the global version from config (c7 layout and instruction set).
+    #[serde(default)]
+    pub global_version: Option<u32>,
+    #[serde(default)]
+    pub trace: TraceMode,
+    #[serde(default)]
+    pub log_limits: Option<LogLimits>,
     pub debug_enabled: bool,
 }
 
+impl AccountGetMethodParams {
+    pub fn resolve_method_id(&self) -> anyhow::Result<i32> {
+        match (&self.method, self.method_id) {
+            (Some(method), _) => Ok(method.resolve()),
+            (None, Some(method_id)) => Ok(method_id),
+            (None, None) => Err(anyhow::anyhow!(
+                "Either `method` or `method_id` is required"
+            ))
+            .with_kind(ErrorKind::InvalidInput),
+        }
+    }
+}
+
+/// Get-method id or its name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum MethodId {
+    Id(i32),
+    Name(String),
+}
+
+impl MethodId {
+    pub fn resolve(&self) -> i32 {
+        match self {
+            Self::Id(id) => *id,
+            Self::Name(name) => method_id_from_name(name),
+        }
+    }
+}
+
+impl std::str::FromStr for MethodId {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse::<i32>() {
+            Ok(id) => Self::Id(id),
+            Err(_) => Self::Name(s.to_owned()),
+        })
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackFormat {
+    /// Base64 encoded BOC of the serialized `VmStack`.
+    #[default]
+    Boc,
+    /// An array of typed JSON stack entries.
+    Json,
+}
+
+/// A single call of a multi-call get-method request.
+#[derive(Deserialize)]
+pub struct GetMethodCall {
+    pub method: MethodId,
+    /// Empty stack is used by default.
+    #[serde(default)]
+    pub stack: Option<GetMethodCallStack>,
+    /// Overrides the gas limit of the shared state.
+    #[serde(default, with = "serde_optional_string")]
+    pub gas_limit: Option<u64>,
+    /// Overrides the format of the resulting stack.
+    #[serde(default)]
+    pub stack_format: Option<StackFormat>,
+    /// Overrides VM log limits of the shared state.
+    #[serde(default)]
+    pub log_limits: Option<LogLimits>,
+}
+
+impl GetMethodCall {
+    /// Runs the call with its overrides on the shared emulator state.
+    pub fn run(
+        self,
+        emulator: &mut TvmEmulator,
+        stack_format: StackFormat,
+    ) -> GetMethodCallResponse {
+        let method_id = self.method.resolve();
+        let overrides = CallOverrides {
+            gas_limit: self.gas_limit,
+            log_limits: self.log_limits,
+        };
+
+        let res = emulator.with_overrides(overrides, |emulator| {
+            let stack = match self.stack {
+                None => Stack::default(),
+                Some(GetMethodCallStack::Boc(stack)) => parse_stack(&stack)?,
+                Some(GetMethodCallStack::Json(items)) => stack_from_json(items)?,
+            };
+            let outcome = emulator.run_get_method_logged(method_id, stack)?;
+            RunGetMethodResponse::new(outcome, self.stack_format.unwrap_or(stack_format))
+        });
+
+        match res {
+            Ok(res) => GetMethodCallResponse::Success(res),
+            Err(e) => GetMethodCallResponse::Error(GetMethodCallErrorResponse {
+                success: JsonBool,
+                error: e.to_string(),
+                error_kind: ErrorKind::of(&e),
+                method_id,
+            }),
+        }
+    }
+}
+
+/// Base64 encoded stack BOC or a typed JSON stack.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum GetMethodCallStack {
+    Boc(String),
+    Json(Vec<JsonStackValue>),
+}
+
+/// Computes get-method id as `crc16(name) | 0x10000`.
+pub fn method_id_from_name(name: &str) -> i32 {
+    (tycho_types::crc::crc_16(name.as_bytes()) as i32) | 0x10000
+}
+
 // === Responses ===
 
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -173,39 +848,161 @@ impl<T: Serialize> Serialize for OkResponse<T> {
 
 pub struct ErrResponse<T> {
     pub message: T,
+    pub error_kind: ErrorKind,
 }
 
 impl<T: std::fmt::Display> Serialize for ErrResponse<T> {
     fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         use serde::ser::SerializeStruct;
 
-        struct Message<'a, T>(&'a T);
+        let mut s = s.serialize_struct("ErrResponse", 3)?;
+        s.serialize_field("ok", &false)?;
+        s.serialize_field("message", &ErrMessage(&self.message))?;
+        s.serialize_field("error_kind", &self.error_kind)?;
+        s.end()
+    }
+}
 
-        impl<T: std::fmt::Display> Serialize for Message<'_, T> {
-            #[inline]
-            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
-                s.collect_str(self.0)
-            }
-        }
+struct ErrMessage<'a, T>(&'a T);
+
+impl<T: std::fmt::Display> Serialize for ErrMessage<'_, T> {
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self.0)
+    }
+}
+
+/// Error response of a get-method with the resolved method id (if any).
+pub struct RunGetMethodErrResponse<T> {
+    pub message: T,
+    pub error_kind: ErrorKind,
+    pub method_id: Option<i32>,
+}
 
-        let mut s = s.serialize_struct("ErrResponse", 2)?;
+impl<T: std::fmt::Display> Serialize for RunGetMethodErrResponse<T> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = s.serialize_struct("RunGetMethodErrResponse", 4)?;
         s.serialize_field("ok", &false)?;
-        s.serialize_field("message", &Message(&self.message))?;
+        s.serialize_field("message", &ErrMessage(&self.message))?;
+        s.serialize_field("error_kind", &self.error_kind)?;
+        s.serialize_field("method_id", &self.method_id)?;
         s.end()
     }
 }
 
 #[derive(Clone, Serialize)]
-pub struct RunGetMethodResponse {
+pub struct ShardAccountResponse {
     pub success: JsonBool<true>,
     #[serde(with = "BocRepr")]
-    pub stack: SafeRc<Stack>,
+    pub shard_account: ShardAccount,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RunGetMethodResponse {
+    pub success: JsonBool<true>,
+    pub method_id: i32,
+    pub stack: StackRepr,
     #[serde(with = "serde_string")]
     pub gas_used: u64,
     pub debug_log: String,
     pub vm_exit_code: i32,
+    pub vm_exit_code_name: Option<&'static str>,
+    pub vm_exit_code_description: Option<&'static str>,
     pub vm_log: VmLogRows,
+    pub vm_log_dropped: VmLogDropped,
     pub missing_library: Option<HashBytes>,
+    /// Global version used for the run.
+    pub vm_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c7: Option<C7Info>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<VmTraceStep>>,
+}
+
+impl RunGetMethodResponse {
+    pub fn new(outcome: GetMethodOutcome, stack_format: StackFormat) -> anyhow::Result<Self> {
+        let GetMethodOutcome {
+            method_id,
+            answer: res,
+            vm_log,
+        } = outcome;
+
+        Ok(Self {
+            success: JsonBool,
+            method_id,
+            stack: StackRepr::new(res.stack, stack_format)?,
+            gas_used: res.gas_used,
+            debug_log: res.debug_log,
+            vm_exit_code: res.exit_code,
+            vm_exit_code_name: exit_code::compute_name(res.exit_code),
+            vm_exit_code_description: exit_code::compute_description(res.exit_code),
+            trace: vm_log.take_trace(),
+            vm_log_dropped: vm_log.dropped(),
+            vm_log,
+            missing_library: res.missing_library,
+            vm_version: res.vm_version,
+            c7: res.c7.map(C7Info::new).transpose()?,
+        })
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum GetMethodCallResponse {
+    Success(RunGetMethodResponse),
+    Error(GetMethodCallErrorResponse),
+}
+
+#[derive(Clone, Serialize)]
+pub struct GetMethodCallErrorResponse {
+    pub success: JsonBool<false>,
+    pub error: String,
+    pub error_kind: ErrorKind,
+    pub method_id: i32,
+}
+
+/// c7 tuple used for the run.
+///
+/// Serialized both as a BOC of the stack value and as a JSON stack entry.
+#[derive(Clone, Serialize)]
+pub struct C7Info {
+    #[serde(with = "Boc")]
+    pub boc: Cell,
+    pub json: JsonStackValue,
+}
+
+impl C7Info {
+    pub fn new(c7: SafeRc<Tuple>) -> anyhow::Result<Self> {
+        let value = c7.into_dyn_value();
+
+        let mut b = CellBuilder::new();
+        value
+            .store_as_stack_value(&mut b, Cell::empty_context())
+            .context("Failed to serialize c7")?;
+        let boc = b.build().context("Failed to serialize c7")?;
+        let json = JsonStackValue::from_stack_value(&value).context("Failed to convert c7")?;
+
+        Ok(Self { boc, json })
+    }
+}
+
+/// Stack in the requested format.
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum StackRepr {
+    Boc(#[serde(with = "BocRepr")] SafeRc<Stack>),
+    Json(Vec<JsonStackValue>),
+}
+
+impl StackRepr {
+    pub fn new(stack: SafeRc<Stack>, format: StackFormat) -> anyhow::Result<Self> {
+        Ok(match format {
+            StackFormat::Boc => Self::Boc(stack),
+            StackFormat::Json => Self::Json(stack_to_json(&stack)?),
+        })
+    }
 }
 
 #[derive(Clone, Serialize)]
@@ -216,23 +1013,91 @@ pub enum TxEmulatorResponse {
     NotAccepted(TxEmulatorMsgNotAcceptedResponse),
 }
 
+impl TxEmulatorResponse {
+    pub fn from_result(res: anyhow::Result<EmulationOutcome>) -> Self {
+        match res.and_then(Self::try_from) {
+            Ok(res) => res,
+            Err(e) => Self::Error(TxEmulatorErrorResponse {
+                success: JsonBool,
+                error: e.to_string(),
+                error_kind: ErrorKind::of(&e),
+                external_not_accepted: JsonBool,
+                debug_log: String::new(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<EmulationOutcome> for TxEmulatorResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(value: EmulationOutcome) -> Result<Self, Self::Error> {
+        Ok(match value {
+            EmulationOutcome::Committed(tx) => {
+                let (description, out_messages) = decode_transaction(&tx.transaction)?;
+                Self::Success(TxEmulatorSuccessResponse {
+                    success: JsonBool,
+                    description,
+                    out_messages,
+                    fees: tx.fees.into(),
+                    state_diff: tx.state_diff,
+                    transaction: tx.transaction.into_inner(),
+                    shard_account: tx.shard_account,
+                    debug_log: tx.debug_log,
+                    trace: tx.vm_log.take_trace(),
+                    vm_log_dropped: tx.vm_log.dropped(),
+                    vm_log: tx.vm_log,
+                    actions: tx.actions,
+                    c7: tx.c7.map(C7Info::new).transpose()?,
+                })
+            }
+            EmulationOutcome::NotAccepted(msg) => {
+                Self::NotAccepted(TxEmulatorMsgNotAcceptedResponse {
+                    success: JsonBool,
+                    error: "External message not accepted by smart contract",
+                    error_kind: ErrorKind::NotAccepted,
+                    external_not_accepted: JsonBool,
+                    debug_log: msg.debug_log,
+                    trace: msg.vm_log.take_trace(),
+                    vm_log_dropped: msg.vm_log.dropped(),
+                    vm_log: msg.vm_log,
+                    vm_exit_code: msg.exit_code,
+                    vm_exit_code_name: exit_code::compute_name(msg.exit_code),
+                    vm_exit_code_description: exit_code::compute_description(msg.exit_code),
+                    c7: msg.c7.map(C7Info::new).transpose()?,
+                })
+            }
+        })
+    }
+}
+
 #[derive(Clone, Serialize)]
 pub struct TxEmulatorSuccessResponse {
     pub success: JsonBool<true>,
     #[serde(with = "Boc")]
     pub transaction: Cell,
+    pub description: TxDescription,
+    pub out_messages: Vec<OutMessage>,
+    pub fees: TxFees,
+    pub state_diff: AccountStateDiff,
     #[serde(with = "BocRepr")]
     pub shard_account: ShardAccount,
     pub debug_log: String,
     pub vm_log: VmLogRows,
+    pub vm_log_dropped: VmLogDropped,
     #[serde(with = "Boc")]
     pub actions: Option<Cell>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c7: Option<C7Info>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<VmTraceStep>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct TxEmulatorErrorResponse {
     pub success: JsonBool<false>,
     pub error: String,
+    pub error_kind: ErrorKind,
     pub external_not_accepted: JsonBool<true>,
     pub debug_log: String,
 }
@@ -241,21 +1106,508 @@ pub struct TxEmulatorErrorResponse {
 pub struct TxEmulatorMsgNotAcceptedResponse {
     pub success: JsonBool<false>,
     pub error: &'static str,
+    pub error_kind: ErrorKind,
     pub external_not_accepted: JsonBool<true>,
     pub debug_log: String,
     pub vm_log: VmLogRows,
+    pub vm_log_dropped: VmLogDropped,
     pub vm_exit_code: i32,
+    pub vm_exit_code_name: Option<&'static str>,
+    pub vm_exit_code_description: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c7: Option<C7Info>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<VmTraceStep>>,
+}
+
+/// Decoded transaction description.
+#[derive(Clone, Serialize)]
+pub struct TxDescription {
+    /// `ordinary`, `tick` or `tock`.
+    pub kind: &'static str,
+    pub credit_first: bool,
+    pub aborted: bool,
+    pub destroyed: bool,
+    #[serde(with = "serde_string")]
+    pub total_fees: u128,
+    pub storage_phase: Option<StoragePhaseDescription>,
+    pub credit_phase: Option<CreditPhaseDescription>,
+    pub compute_phase: ComputePhaseDescription,
+    pub action_phase: Option<ActionPhaseDescription>,
+    pub bounce_phase: Option<BouncePhaseDescription>,
+}
+
+impl TxDescription {
+    pub fn new(tx: &Transaction) -> anyhow::Result<Self> {
+        let total_fees = tx.total_fees.tokens.into_inner();
+        Ok(match tx.info.load()? {
+            TxInfo::Ordinary(info) => Self {
+                kind: "ordinary",
+                credit_first: info.credit_first,
+                aborted: info.aborted,
+                destroyed: info.destroyed,
+                total_fees,
+                storage_phase: info.storage_phase.as_ref().map(From::from),
+                credit_phase: info.credit_phase.as_ref().map(From::from),
+                compute_phase: (&info.compute_phase).into(),
+                action_phase: info.action_phase.as_ref().map(From::from),
+                bounce_phase: info.bounce_phase.as_ref().map(From::from),
+            },
+            TxInfo::TickTock(info) => Self {
+                kind: match info.kind {
+                    TickTock::Tick => "tick",
+                    TickTock::Tock => "tock",
+                },
+                credit_first: false,
+                aborted: info.aborted,
+                destroyed: info.destroyed,
+                total_fees,
+                storage_phase: Some((&info.storage_phase).into()),
+                credit_phase: None,
+                compute_phase: (&info.compute_phase).into(),
+                action_phase: info.action_phase.as_ref().map(From::from),
+                bounce_phase: None,
+            },
+        })
+    }
+}
+
+/// Fees charged by the transaction.
+#[derive(Clone, Serialize)]
+pub struct TxFees {
+    #[serde(with = "serde_string")]
+    pub storage_fees_collected: u128,
+    #[serde(with = "serde_string")]
+    pub storage_fees_due: u128,
+    #[serde(with = "serde_string")]
+    pub gas_fees: u128,
+    #[serde(with = "serde_string")]
+    pub action_fees: u128,
+    #[serde(with = "serde_string")]
+    pub total_fwd_fees: u128,
+    #[serde(with = "serde_string")]
+    pub bounce_fees: u128,
+    #[serde(with = "serde_string")]
+    pub total_fees: u128,
+    pub out_msgs: Vec<OutMessageFees>,
+}
+
+impl From<TransactionFees> for TxFees {
+    fn from(value: TransactionFees) -> Self {
+        Self {
+            storage_fees_collected: value.storage_fees_collected.into_inner(),
+            storage_fees_due: value.storage_fees_due.into_inner(),
+            gas_fees: value.gas_fees.into_inner(),
+            action_fees: value.action_fees.into_inner(),
+            total_fwd_fees: value.total_fwd_fees.into_inner(),
+            bounce_fees: value.bounce_fees.into_inner(),
+            total_fees: value.total_fees.into_inner(),
+            out_msgs: value
+                .out_msgs
+                .into_iter()
+                .map(|fees| OutMessageFees {
+                    hash: fees.hash,
+                    fwd_fee: fees.fwd_fee.into_inner(),
+                    action_fee: fees.action_fee.into_inner(),
+                    remaining_fwd_fee: fees.remaining_fwd_fee.into_inner(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct OutMessageFees {
+    pub hash: HashBytes,
+    #[serde(with = "serde_string")]
+    pub fwd_fee: u128,
+    #[serde(with = "serde_string")]
+    pub action_fee: u128,
+    #[serde(with = "serde_string")]
+    pub remaining_fwd_fee: u128,
+}
+
+/// Decoded outbound message of the transaction.
+#[derive(Clone, Serialize)]
+pub struct OutMessage {
+    #[serde(with = "Boc")]
+    pub boc: Cell,
+    pub hash: HashBytes,
+    /// `int` or `ext_out`.
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    pub src: String,
+    pub dst: Option<String>,
+    #[serde(with = "serde_optional_string")]
+    pub value: Option<u128>,
+    #[serde(with = "serde_extra_currencies")]
+    pub value_extra: ExtraCurrencyCollection,
+    pub bounce: Option<bool>,
+    pub bounced: Option<bool>,
+    #[serde(with = "serde_optional_string")]
+    pub fwd_fee: Option<u128>,
+    #[serde(with = "serde_string")]
+    pub created_lt: u64,
+    pub created_at: u32,
+}
+
+impl OutMessage {
+    pub fn new(message: Cell) -> anyhow::Result<Self> {
+        let hash = *message.repr_hash();
+        Ok(match message.parse::<MsgInfo>()? {
+            MsgInfo::Int(info) => Self {
+                boc: message,
+                hash,
+                ty: "int",
+                src: info.src.to_string(),
+                dst: Some(info.dst.to_string()),
+                value: Some(info.value.tokens.into_inner()),
+                value_extra: info.value.other,
+                bounce: Some(info.bounce),
+                bounced: Some(info.bounced),
+                fwd_fee: Some(info.fwd_fee.into_inner()),
+                created_lt: info.created_lt,
+                created_at: info.created_at,
+            },
+            MsgInfo::ExtOut(info) => Self {
+                boc: message,
+                hash,
+                ty: "ext_out",
+                src: info.src.to_string(),
+                dst: info.dst.map(|addr| addr.to_string()),
+                value: None,
+                value_extra: ExtraCurrencyCollection::new(),
+                bounce: None,
+                bounced: None,
+                fwd_fee: None,
+                created_lt: info.created_lt,
+                created_at: info.created_at,
+            },
+            MsgInfo::ExtIn(_) => anyhow::bail!("unexpected outbound ext_in message"),
+        })
+    }
+
+    /// Decodes all outbound messages in the order of their indices.
+    pub fn load_all(tx: &Transaction) -> anyhow::Result<Vec<Self>> {
+        let mut result = Vec::with_capacity(tx.out_msg_count.into_inner() as usize);
+        for msg in tx.out_msgs.values() {
+            result.push(Self::new(msg?)?);
+        }
+        Ok(result)
+    }
+}
+
+fn decode_transaction(tx: &Lazy<Transaction>) -> anyhow::Result<(TxDescription, Vec<OutMessage>)> {
+    let tx = tx.load().context("Failed to load transaction")?;
+    let description =
+        TxDescription::new(&tx).context("Failed to decode transaction description")?;
+    let out_messages = OutMessage::load_all(&tx).context("Failed to decode outbound messages")?;
+    Ok((description, out_messages))
+}
+
+#[derive(Clone, Serialize)]
+pub struct StoragePhaseDescription {
+    #[serde(with = "serde_string")]
+    pub storage_fees_collected: u128,
+    #[serde(with = "serde_optional_string")]
+    pub storage_fees_due: Option<u128>,
+    pub status_change: &'static str,
+}
+
+impl From<&StoragePhase> for StoragePhaseDescription {
+    fn from(value: &StoragePhase) -> Self {
+        Self {
+            storage_fees_collected: value.storage_fees_collected.into_inner(),
+            storage_fees_due: value.storage_fees_due.map(|x| x.into_inner()),
+            status_change: status_change_name(value.status_change),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct CreditPhaseDescription {
+    #[serde(with = "serde_optional_string")]
+    pub due_fees_collected: Option<u128>,
+    #[serde(with = "serde_string")]
+    pub credit: u128,
+    #[serde(with = "serde_extra_currencies")]
+    pub credit_extra: ExtraCurrencyCollection,
+}
+
+impl From<&CreditPhase> for CreditPhaseDescription {
+    fn from(value: &CreditPhase) -> Self {
+        Self {
+            due_fees_collected: value.due_fees_collected.map(|x| x.into_inner()),
+            credit: value.credit.tokens.into_inner(),
+            credit_extra: value.credit.other.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ComputePhaseDescription {
+    Skipped {
+        /// `no_state`, `bad_state`, `no_gas` or `suspended`.
+        reason: &'static str,
+    },
+    Executed {
+        success: bool,
+        msg_state_used: bool,
+        account_activated: bool,
+        #[serde(with = "serde_string")]
+        gas_fees: u128,
+        #[serde(with = "serde_string")]
+        gas_used: u64,
+        #[serde(with = "serde_string")]
+        gas_limit: u64,
+        #[serde(with = "serde_optional_string")]
+        gas_credit: Option<u32>,
+        mode: i8,
+        exit_code: i32,
+        exit_code_name: Option<&'static str>,
+        exit_code_description: Option<&'static str>,
+        exit_arg: Option<i32>,
+        vm_steps: u32,
+        vm_init_state_hash: HashBytes,
+        vm_final_state_hash: HashBytes,
+    },
+}
+
+impl From<&ComputePhase> for ComputePhaseDescription {
+    fn from(value: &ComputePhase) -> Self {
+        match value {
+            ComputePhase::Skipped(phase) => Self::Skipped {
+                reason: match phase.reason {
+                    ComputePhaseSkipReason::NoState => "no_state",
+                    ComputePhaseSkipReason::BadState => "bad_state",
+                    ComputePhaseSkipReason::NoGas => "no_gas",
+                    ComputePhaseSkipReason::Suspended => "suspended",
+                },
+            },
+            ComputePhase::Executed(phase) => Self::Executed {
+                success: phase.success,
+                msg_state_used: phase.msg_state_used,
+                account_activated: phase.account_activated,
+                gas_fees: phase.gas_fees.into_inner(),
+                gas_used: phase.gas_used.into_inner(),
+                gas_limit: phase.gas_limit.into_inner(),
+                gas_credit: phase.gas_credit.map(|x| x.into_inner()),
+                mode: phase.mode,
+                exit_code: phase.exit_code,
+                exit_code_name: exit_code::compute_name(phase.exit_code),
+                exit_code_description: exit_code::compute_description(phase.exit_code),
+                exit_arg: phase.exit_arg,
+                vm_steps: phase.vm_steps,
+                vm_init_state_hash: phase.vm_init_state_hash,
+                vm_final_state_hash: phase.vm_final_state_hash,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ActionPhaseDescription {
+    pub success: bool,
+    pub valid: bool,
+    pub no_funds: bool,
+    pub status_change: &'static str,
+    #[serde(with = "serde_optional_string")]
+    pub total_fwd_fees: Option<u128>,
+    #[serde(with = "serde_optional_string")]
+    pub total_action_fees: Option<u128>,
+    pub result_code: i32,
+    pub result_code_name: Option<&'static str>,
+    pub result_code_description: Option<&'static str>,
+    pub result_arg: Option<i32>,
+    pub total_actions: u16,
+    pub special_actions: u16,
+    pub skipped_actions: u16,
+    pub messages_created: u16,
+    pub action_list_hash: HashBytes,
+    #[serde(with = "serde_string")]
+    pub total_message_cells: u64,
+    #[serde(with = "serde_string")]
+    pub total_message_bits: u64,
+}
+
+impl From<&ActionPhase> for ActionPhaseDescription {
+    fn from(value: &ActionPhase) -> Self {
+        Self {
+            success: value.success,
+            valid: value.valid,
+            no_funds: value.no_funds,
+            status_change: status_change_name(value.status_change),
+            total_fwd_fees: value.total_fwd_fees.map(|x| x.into_inner()),
+            total_action_fees: value.total_action_fees.map(|x| x.into_inner()),
+            result_code: value.result_code,
+            result_code_name: exit_code::action_name(value.result_code),
+            result_code_description: exit_code::action_description(value.result_code),
+            result_arg: value.result_arg,
+            total_actions: value.total_actions,
+            special_actions: value.special_actions,
+            skipped_actions: value.skipped_actions,
+            messages_created: value.messages_created,
+            action_list_hash: value.action_list_hash,
+            total_message_cells: value.total_message_size.cells.into_inner(),
+            total_message_bits: value.total_message_size.bits.into_inner(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BouncePhaseDescription {
+    NegativeFunds,
+    NoFunds {
+        #[serde(with = "serde_string")]
+        msg_cells: u64,
+        #[serde(with = "serde_string")]
+        msg_bits: u64,
+        #[serde(with = "serde_string")]
+        req_fwd_fees: u128,
+    },
+    Executed {
+        #[serde(with = "serde_string")]
+        msg_cells: u64,
+        #[serde(with = "serde_string")]
+        msg_bits: u64,
+        #[serde(with = "serde_string")]
+        msg_fees: u128,
+        #[serde(with = "serde_string")]
+        fwd_fees: u128,
+    },
+}
+
+impl From<&BouncePhase> for BouncePhaseDescription {
+    fn from(value: &BouncePhase) -> Self {
+        match value {
+            BouncePhase::NegativeFunds => Self::NegativeFunds,
+            BouncePhase::NoFunds(phase) => Self::NoFunds {
+                msg_cells: phase.msg_size.cells.into_inner(),
+                msg_bits: phase.msg_size.bits.into_inner(),
+                req_fwd_fees: phase.req_fwd_fees.into_inner(),
+            },
+            BouncePhase::Executed(phase) => Self::Executed {
+                msg_cells: phase.msg_size.cells.into_inner(),
+                msg_bits: phase.msg_size.bits.into_inner(),
+                msg_fees: phase.msg_fees.into_inner(),
+                fwd_fees: phase.fwd_fees.into_inner(),
+            },
+        }
+    }
+}
+
+fn status_change_name(status_change: AccountStatusChange) -> &'static str {
+    match status_change {
+        AccountStatusChange::Unchanged => "unchanged",
+        AccountStatusChange::Frozen => "frozen",
+        AccountStatusChange::Deleted => "deleted",
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum LocalShardResponse {
+    Success(LocalShardSuccessResponse),
+    Error(TxEmulatorErrorResponse),
+    NotAccepted(TxEmulatorMsgNotAcceptedResponse),
+}
+
+impl TryFrom<LocalShardOutcome> for LocalShardResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(value: LocalShardOutcome) -> Result<Self, Self::Error> {
+        Ok(match value {
+            LocalShardOutcome::Committed(tree) => Self::Success(LocalShardSuccessResponse {
+                success: JsonBool,
+                transactions: tree
+                    .transactions
+                    .into_iter()
+                    .map(|node| {
+                        let (description, out_messages) = decode_transaction(&node.tx.transaction)?;
+                        Ok(LocalShardTransaction {
+                            address: node.address,
+                            parent: node.parent,
+                            children: node.children,
+                            description,
+                            out_messages,
+                            fees: node.tx.fees.into(),
+                            state_diff: node.tx.state_diff,
+                            transaction: node.tx.transaction.into_inner(),
+                            shard_account: node.tx.shard_account,
+                            debug_log: node.tx.debug_log,
+                            trace: node.tx.vm_log.take_trace(),
+                            vm_log_dropped: node.tx.vm_log.dropped(),
+                            vm_log: node.tx.vm_log,
+                            actions: node.tx.actions,
+                            c7: node.tx.c7.map(C7Info::new).transpose()?,
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            }),
+            LocalShardOutcome::NotAccepted(msg) => {
+                Self::NotAccepted(TxEmulatorMsgNotAcceptedResponse {
+                    success: JsonBool,
+                    error: "External message not accepted by smart contract",
+                    error_kind: ErrorKind::NotAccepted,
+                    external_not_accepted: JsonBool,
+                    debug_log: msg.debug_log,
+                    trace: msg.vm_log.take_trace(),
+                    vm_log_dropped: msg.vm_log.dropped(),
+                    vm_log: msg.vm_log,
+                    vm_exit_code: msg.exit_code,
+                    vm_exit_code_name: exit_code::compute_name(msg.exit_code),
+                    vm_exit_code_description: exit_code::compute_description(msg.exit_code),
+                    c7: msg.c7.map(C7Info::new).transpose()?,
+                })
+            }
+        })
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct LocalShardSuccessResponse {
+    pub success: JsonBool<true>,
+    pub transactions: Vec<LocalShardTransaction>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LocalShardTransaction {
+    #[serde(with = "serde_string")]
+    pub address: StdAddr,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    #[serde(with = "Boc")]
+    pub transaction: Cell,
+    pub description: TxDescription,
+    pub out_messages: Vec<OutMessage>,
+    pub fees: TxFees,
+    pub state_diff: AccountStateDiff,
+    #[serde(with = "BocRepr")]
+    pub shard_account: ShardAccount,
+    pub debug_log: String,
+    pub vm_log: VmLogRows,
+    pub vm_log_dropped: VmLogDropped,
+    #[serde(with = "Boc")]
+    pub actions: Option<Cell>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c7: Option<C7Info>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<VmTraceStep>>,
 }
 
-#[cfg(feature = "native")]
 #[derive(Clone, Serialize)]
 pub struct TvmEmulatorSendMessageResponse {
     pub success: JsonBool<true>,
     #[serde(with = "serde_string")]
     pub gas_used: u64,
     pub vm_exit_code: i32,
+    pub vm_exit_code_name: Option<&'static str>,
+    pub vm_exit_code_description: Option<&'static str>,
     pub accepted: bool,
     pub vm_log: VmLogRows,
+    pub vm_log_dropped: VmLogDropped,
     pub missing_library: Option<HashBytes>,
     #[serde(with = "Boc")]
     pub actions: Option<Cell>,
@@ -263,12 +1615,42 @@ pub struct TvmEmulatorSendMessageResponse {
     pub new_code: Cell,
     #[serde(with = "Boc")]
     pub new_data: Cell,
+    pub vm_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c7: Option<C7Info>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Vec<VmTraceStep>>,
+}
+
+impl TvmEmulatorSendMessageResponse {
+    pub fn new(res: Answer, vm_log: VmLogRows) -> anyhow::Result<Self> {
+        Ok(Self {
+            success: JsonBool,
+            gas_used: res.gas_used,
+            vm_exit_code: res.exit_code,
+            vm_exit_code_name: exit_code::compute_name(res.exit_code),
+            vm_exit_code_description: exit_code::compute_description(res.exit_code),
+            accepted: res.accepted,
+            trace: vm_log.take_trace(),
+            vm_log_dropped: vm_log.dropped(),
+            vm_log,
+            missing_library: res.missing_library,
+            actions: res.actions,
+            new_code: res.code,
+            new_data: res.data,
+            vm_version: res.vm_version,
+            c7: res.c7.map(C7Info::new).transpose()?,
+        })
+    }
 }
 
 #[cfg(feature = "native")]
 #[derive(Debug, Clone, Copy)]
 pub struct TvmEmulatorErrorResponse<'a> {
     pub error: &'a str,
+    pub error_kind: ErrorKind,
+    /// Resolved get-method id (if any).
+    pub method_id: Option<i32>,
 }
 
 #[cfg(feature = "native")]
@@ -279,10 +1661,255 @@ impl Serialize for TvmEmulatorErrorResponse<'_> {
     {
         use serde::ser::SerializeStruct;
 
-        let mut s = serializer.serialize_struct("TvmEmulatorErrorResponse", 3)?;
+        let mut s = serializer.serialize_struct("TvmEmulatorErrorResponse", 5)?;
         s.serialize_field("success", &false)?;
         s.serialize_field("error", self.error)?;
+        s.serialize_field("error_kind", &self.error_kind)?;
         s.serialize_field("external_not_accepted", &false)?;
+        if let Some(method_id) = self.method_id {
+            s.serialize_field("method_id", &method_id)?;
+        }
         s.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        NOW, SEND_ONE, addr, int_msg, make_account, make_emulator, make_params,
+    };
+
+    fn emulate(account: &ShardAccount, msg: Cell) -> TxEmulatorSuccessResponse {
+        let outcome = make_emulator()
+            .emulate(account, EmulationInput::Ordinary(msg), &make_params())
+            .unwrap();
+        match TxEmulatorResponse::try_from(outcome).unwrap() {
+            TxEmulatorResponse::Success(res) => res,
+            _ => panic!("transaction must be committed"),
+        }
+    }
+
+    #[test]
+    fn method_id_from_crc16() {
+        assert_eq!(method_id_from_name("seqno"), 85143);
+        assert_eq!(method_id_from_name("get_public_key"), 78748);
+
+        assert_eq!("seqno".parse::<MethodId>().unwrap().resolve(), 85143);
+        assert_eq!("85143".parse::<MethodId>().unwrap().resolve(), 85143);
+        assert_eq!("-1".parse::<MethodId>().unwrap().resolve(), -1);
+    }
+
+    #[test]
+    fn return_c7() {
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        let outcome = emulator.run_get_method_logged(0, Stack::default()).unwrap();
+        let res = RunGetMethodResponse::new(outcome, StackFormat::Boc).unwrap();
+        assert!(res.c7.is_none());
+
+        emulator.args.return_c7 = true;
+        let outcome = emulator.run_get_method_logged(0, Stack::default()).unwrap();
+        let res = RunGetMethodResponse::new(outcome, StackFormat::Boc).unwrap();
+        let c7 = res.c7.unwrap();
+
+        let JsonStackValue::Tuple { value: items } = &c7.json else {
+            panic!("c7 must be a tuple");
+        };
+        let [JsonStackValue::Tuple { value: smc_info }] = items.as_slice() else {
+            panic!("c7 must contain a single smc info tuple");
+        };
+        let JsonStackValue::Int { value: magic } = &smc_info[0] else {
+            panic!("smc info must start with a magic");
+        };
+        assert_eq!(*magic, num_bigint::BigInt::from(0x076ef1eau32));
+
+        let json = serde_json::to_value(&c7).unwrap();
+        assert_eq!(json["boc"], Boc::encode_base64(&c7.boc));
+        assert_eq!(json["json"]["type"], "tuple");
+    }
+
+    #[test]
+    fn get_method_calls_share_state() {
+        // NOW
+        let code = CellBuilder::build_from(0xf823u16).unwrap();
+        let mut emulator = TvmEmulator::new(code, Cell::empty_cell(), 0);
+        emulator.args.now = Some(123);
+
+        let call = |gas_limit, stack| GetMethodCall {
+            method: MethodId::Id(0),
+            stack,
+            gas_limit,
+            stack_format: Some(StackFormat::Json),
+            log_limits: Some(LogLimits {
+                max_rows: Some(1),
+                ..Default::default()
+            }),
+        };
+
+        // Gas limit override is applied only to its call.
+        let GetMethodCallResponse::Success(res) =
+            call(Some(1), None).run(&mut emulator, StackFormat::Boc)
+        else {
+            panic!("call must succeed");
+        };
+        assert_ne!(res.vm_exit_code, 0);
+
+        for _ in 0..2 {
+            let GetMethodCallResponse::Success(res) =
+                call(None, None).run(&mut emulator, StackFormat::Boc)
+            else {
+                panic!("call must succeed");
+            };
+            assert_eq!(res.vm_exit_code, 0);
+
+            let StackRepr::Json(stack) = &res.stack else {
+                panic!("json stack expected");
+            };
+            let Some(JsonStackValue::Int { value }) = stack.last() else {
+                panic!("int expected");
+            };
+            assert_eq!(*value, num_bigint::BigInt::from(123));
+        }
+
+        // Overrides are restored when the call fails.
+        let stack = Some(GetMethodCallStack::Boc("invalid".to_owned()));
+        let GetMethodCallResponse::Error(res) =
+            call(Some(1000), stack).run(&mut emulator, StackFormat::Boc)
+        else {
+            panic!("call must fail");
+        };
+        assert_eq!(res.method_id, 0);
+        assert!(emulator.args.gas_limit.is_none());
+        assert!(emulator.args.log_limits.max_rows.is_none());
+    }
+
+    #[test]
+    fn decode_description() {
+        let account = crate::local_shard::empty_shard_account().unwrap();
+        let res = emulate(&account, int_msg(&addr(0xff), &addr(1), false, &[]));
+
+        let description = res.description;
+        assert_eq!(description.kind, "ordinary");
+        assert!(!description.credit_first);
+        assert!(!description.destroyed);
+        assert_eq!(description.total_fees, res.fees.total_fees);
+        assert!(description.storage_phase.is_some());
+        assert_eq!(description.credit_phase.unwrap().credit, 100_000_000);
+        assert!(matches!(
+            description.compute_phase,
+            ComputePhaseDescription::Skipped { reason: "no_state" }
+        ));
+        assert!(description.action_phase.is_none());
+        assert!(res.out_messages.is_empty());
+    }
+
+    #[test]
+    fn shard_account_storage_stat() {
+        let mut data = CellBuilder::new();
+        data.store_u32(42).unwrap();
+        let state_init = StateInit {
+            code: Some(Cell::empty_cell()),
+            data: Some(data.build().unwrap()),
+            ..Default::default()
+        };
+        let address = StdAddr::new(
+            0,
+            *CellBuilder::build_from(&state_init).unwrap().repr_hash(),
+        );
+
+        // Deploy the account to get the storage stats computed by the executor.
+        let msg = CellBuilder::build_from(OwnedMessage {
+            info: MsgInfo::Int(IntMsgInfo {
+                ihr_disabled: true,
+                bounce: false,
+                bounced: false,
+                src: addr(0xff).into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000_000),
+                extra_flags: Default::default(),
+                fwd_fee: Tokens::ZERO,
+                created_lt: 0,
+                created_at: 0,
+            }),
+            init: Some(state_init),
+            body: Cell::empty_cell().into(),
+            layout: None,
+        })
+        .unwrap();
+        let account = crate::local_shard::empty_shard_account().unwrap();
+        let res = emulate(&account, msg);
+        assert_eq!(res.state_diff.status_after, "active");
+
+        let deployed = res.shard_account.load_account().unwrap().unwrap();
+        let built = make_shard_account(
+            address,
+            deployed.balance.clone(),
+            deployed.state.clone(),
+            deployed.storage_stat.last_paid,
+            deployed.last_trans_lt,
+        )
+        .unwrap();
+        let built = built.load_account().unwrap().unwrap();
+        assert_eq!(built.storage_stat.used, deployed.storage_stat.used);
+    }
+
+    #[test]
+    fn uninit_shard_account_with_state() {
+        let params = |code: Option<Cell>| ShardAccountParams {
+            address: addr(1),
+            balance: 1_000_000_000,
+            extra_currencies: ExtraCurrencyCollection::new(),
+            state: ShardAccountState::Uninit,
+            code,
+            data: None,
+            last_paid: NOW,
+            last_trans_lt: 0,
+        };
+
+        let account = params(None).build().unwrap();
+        let account = account.load_account().unwrap().unwrap();
+        assert!(matches!(account.state, AccountState::Uninit));
+
+        assert!(params(Some(Cell::empty_cell())).build().is_err());
+    }
+
+    #[test]
+    fn decode_out_messages() {
+        let (user, a, b) = (addr(0xff), addr(1), addr(2));
+
+        let account = make_account(&a, SEND_ONE);
+        let msg = int_msg(&user, &a, false, &[int_msg(&a, &b, false, &[])]);
+        let res = emulate(&account, msg);
+
+        let ComputePhaseDescription::Executed {
+            success, exit_code, ..
+        } = res.description.compute_phase
+        else {
+            panic!("compute phase must be executed");
+        };
+        assert!(success);
+        assert_eq!(exit_code, 0);
+
+        let action = res.description.action_phase.unwrap();
+        assert!(action.success);
+        assert_eq!(action.total_actions, 1);
+        assert_eq!(action.result_code, 0);
+
+        let [msg] = res.out_messages.as_slice() else {
+            panic!("one outbound message expected");
+        };
+        assert_eq!(msg.ty, "int");
+        assert_eq!(msg.src, a.to_string());
+        assert_eq!(msg.dst, Some(b.to_string()));
+        assert_eq!(msg.hash, *msg.boc.repr_hash());
+        assert_eq!(
+            msg.fwd_fee,
+            action
+                .total_fwd_fees
+                .map(|fee| fee - action.total_action_fees.unwrap_or(0))
+        );
+        assert!(msg.created_lt > res.transaction.parse::<Transaction>().unwrap().lt);
+        assert_eq!(res.fees.out_msgs.len(), 1);
+        assert_eq!(res.fees.out_msgs[0].hash, msg.hash);
+    }
+}
@@ -2,24 +2,37 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
 use std::ffi::{CStr, c_char, c_int, c_void};
-use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use anyhow::{Context, Result};
-use tycho_types::models::{IntAddr, LibDescr, MsgInfo, ShardAccount, StdAddr, TickTock};
+use tycho_types::models::{ExtraCurrencyCollection, LibDescr, ShardAccount, StdAddr, TickTock};
+use tycho_types::num::VarUint248;
 use tycho_types::prelude::*;
-use tycho_vm::{SafeRc, Stack, Tuple, TupleExt};
+use tycho_vm::{Stack, Tuple, TupleExt, VmLogMask};
 
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::json_stack;
+use crate::local_shard::{LocalShard, LocalShardOutcome};
 use crate::models::{
-    RunGetMethodResponse, TvmEmulatorErrorResponse, TvmEmulatorSendMessageResponse,
-    TxEmulatorMsgNotAcceptedResponse, TxEmulatorResponse, TxEmulatorSuccessResponse, VersionInfo,
+    EmulationJobParams, GetMethodCall, GetMethodCallResponse, InternalMessageParams,
+    LocalShardResponse, MethodId, RunGetMethodResponse, ShardAccountParams, ShardAccountResponse,
+    StackFormat, TvmEmulatorErrorResponse, TvmEmulatorSendMessageResponse, TxEmulatorResponse,
+    VersionInfo,
 };
-use crate::tvm_emulator::{self, TvmEmulator};
-use crate::tx_emulator::TxEmulator;
-use crate::util::{JsonBool, now_sec_u64};
+use crate::subscriber::{LogLimits, LogSink, TraceMode};
+use crate::tvm_emulator::{self, TvmEmulator, TvmEmulatorSnapshot};
+use crate::tx_emulator::{
+    EmulatedTransaction, EmulationInput, EmulationJob, EmulationOutcome, EmulationParams,
+    TxEmulator,
+};
+use crate::util::{JsonBool, parse_prev_blocks_info, resolve_vm_log_mask, serde_extra_currencies};
 
 static VERBOSITY_LEVEL: AtomicU32 = AtomicU32::new(0);
 
+/// VM log verbosity of run method calls without an explicit verbosity.
+const DEFAULT_VM_LOG_VERBOSITY: i32 = 3;
+
 // === FFI Stuff ===
 
 #[unsafe(no_mangle)]
@@ -71,6 +84,21 @@ pub unsafe extern "C" fn emulator_config_destroy(config: *mut c_void) {
     ffi_drop::<tvm_emulator::ParsedConfig>(config)
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn emulator_build_shard_account(params_json: *const c_char) -> *mut c_char {
+    ffi_run_with_response(|| {
+        let params_json = parse_str(params_json)?;
+        let params = serde_json::from_str::<ShardAccountParams>(params_json)
+            .context("Failed to parse account params")
+            .with_kind(ErrorKind::InvalidInput)?;
+
+        Ok(ShardAccountResponse {
+            success: JsonBool,
+            shard_account: params.build().with_kind(ErrorKind::InvalidInput)?,
+        })
+    })
+}
+
 // === Transaction Emulator ===
 
 #[unsafe(no_mangle)]
@@ -87,6 +115,11 @@ pub unsafe extern "C" fn transaction_emulator_create(
             libraries: Dict::new(),
             prev_blocks_info: None,
             debug_enabled: false,
+            data_cells_diff: false,
+            return_c7: false,
+            trace: TraceMode::Disabled,
+            auto_advance: false,
+            unixtime_step: 0,
         }))
     })
 }
@@ -120,6 +153,20 @@ pub unsafe extern "C" fn transaction_emulator_set_lt(
     })
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_auto_advance(
+    transaction_emulator: *mut c_void,
+    enabled: bool,
+    unixtime_step: u32,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.auto_advance = enabled;
+        emulator.unixtime_step = unixtime_step;
+        Ok(())
+    })
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn transaction_emulator_set_rand_seed(
     transaction_emulator: *mut c_void,
@@ -201,6 +248,91 @@ pub unsafe extern "C" fn transaction_emulator_set_debug_enabled(
     })
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_data_cells_diff(
+    transaction_emulator: *mut c_void,
+    data_cells_diff: bool,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.data_cells_diff = data_cells_diff;
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_return_c7(
+    transaction_emulator: *mut c_void,
+    return_c7: bool,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.return_c7 = return_c7;
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_trace(
+    transaction_emulator: *mut c_void,
+    trace_level: c_int,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.trace = TraceMode::from_level(trace_level);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_vm_log_verbosity(
+    transaction_emulator: *mut c_void,
+    vm_log_verbosity: c_int,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.base.set_verbosity(vm_log_verbosity);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_vm_log_mask(
+    transaction_emulator: *mut c_void,
+    vm_log_mask: u32,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.base.vm_modifiers.log_mask = VmLogMask::from_bits_truncate(vm_log_mask as _);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_log_limits(
+    transaction_emulator: *mut c_void,
+    limits_json: *const c_char,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.base.log_limits = parse_log_limits(limits_json)?;
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_set_log_callback(
+    transaction_emulator: *mut c_void,
+    callback: Option<LogCallback>,
+    user_data: *mut c_void,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        emulator.base.log_sink = make_log_sink(callback, user_data);
+        Ok(())
+    })
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn transaction_emulator_set_prev_blocks_info(
     transaction_emulator: *mut c_void,
@@ -213,16 +345,10 @@ pub unsafe extern "C" fn transaction_emulator_set_prev_blocks_info(
         }
 
         let info_cell = parse_boc(info_boc).context("Failed to deserialize previous blocks boc")?;
-        let info_value = Stack::load_stack_value_from_cell(info_cell.as_ref())
-            .context("Failed to deserialize previous blocks tuple")?;
 
-        if info_value.is_null() {
-            emulator.prev_blocks_info = None;
-        } else if let Ok(tuple) = info_value.into_tuple() {
-            emulator.prev_blocks_info = Some(tuple);
-        } else {
-            anyhow::bail!("Failed to set previous blocks tuple: not a tuple");
-        }
+        // NOTE: Only the raw cell is stored to keep the emulator `Sync`.
+        let info = parse_prev_blocks_info(Some(&info_cell))?;
+        emulator.prev_blocks_info = info.map(|_| info_cell);
         Ok(())
     })
 }
@@ -235,13 +361,31 @@ pub unsafe extern "C" fn transaction_emulator_emulate_transaction(
 ) -> *mut c_char {
     ffi_run_with_response(|| {
         let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
-        let message = parse_boc(message_boc)?;
+        let message = parse_boc(message_boc).context("Failed to deserialize message")?;
+        let account = parse_shard_account(shard_account_boc)?;
+
+        emulator.emulate(
+            account,
+            EmulationInput::Ordinary(message),
+            &Default::default(),
+        )
+    })
+}
 
-        let account = parse_boc(shard_account_boc)?
-            .parse::<ShardAccount>()
-            .context("Failed to unpack shard account")?;
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_emulate_transaction_ext(
+    transaction_emulator: *mut c_void,
+    shard_account_boc: *const c_char,
+    message_boc: *const c_char,
+    params_json: *const c_char,
+) -> *mut c_char {
+    ffi_run_with_response(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        let message = parse_boc(message_boc).context("Failed to deserialize message")?;
+        let account = parse_shard_account(shard_account_boc)?;
+        let call = parse_call_params(params_json)?;
 
-        emulator.emulate(account, TxEmulatorInput::Ordinary(message))
+        emulator.emulate(account, EmulationInput::Ordinary(message), &call)
     })
 }
 
@@ -253,12 +397,110 @@ pub unsafe extern "C" fn transaction_emulator_emulate_tick_tock_transaction(
 ) -> *mut c_char {
     ffi_run_with_response(|| {
         let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        let account = parse_shard_account(shard_account_boc)?;
+
+        let ty = if is_tock {
+            TickTock::Tock
+        } else {
+            TickTock::Tick
+        };
+        emulator.emulate(account, EmulationInput::TickTock(ty), &Default::default())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transaction_emulator_emulate_batch(
+    transaction_emulator: *mut c_void,
+    jobs_json: *const c_char,
+    threads: u32,
+) -> *mut c_char {
+    ffi_run_with_response(|| {
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+
+        let jobs_json = parse_str(jobs_json)?;
+        let jobs = serde_json::from_str::<Vec<EmulationJobParams>>(jobs_json)
+            .context("Failed to parse emulation jobs")
+            .with_kind(ErrorKind::InvalidInput)?
+            .into_iter()
+            .map(EmulationJob::from)
+            .collect::<Vec<_>>();
+
+        emulator.emulate_batch(jobs, threads)
+    })
+}
 
-        let account = parse_boc(shard_account_boc)?
-            .parse::<ShardAccount>()
-            .context("Failed to unpack shard account")?;
+// === Local Shard ===
 
-        emulator.emulate(account, TxEmulatorInput::TickTock { is_tock })
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn local_shard_create(max_transactions: u32) -> *mut c_void {
+    ffi_new::<LocalShard, _>(|| {
+        let mut shard = LocalShard::new();
+        if max_transactions > 0 {
+            shard = shard.with_max_transactions(max_transactions as usize);
+        }
+        Ok(Box::new(shard))
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn local_shard_destroy(local_shard: *mut c_void) {
+    ffi_drop::<LocalShard>(local_shard)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn local_shard_set_account(
+    local_shard: *mut c_void,
+    address: *const c_char,
+    shard_account_boc: *const c_char,
+) -> bool {
+    ffi_run(|| {
+        let address = parse_std_addr(address).context("Failed to parse address")?;
+        let account = parse_shard_account(shard_account_boc)?;
+
+        let shard = ffi_cast_mut::<LocalShard>(local_shard)?;
+        shard.set_account(address, account);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn local_shard_get_account(
+    local_shard: *mut c_void,
+    address: *const c_char,
+) -> *mut c_char {
+    let res = (|| {
+        let address = parse_std_addr(address).context("Failed to parse address")?;
+        let shard = ffi_cast::<LocalShard>(local_shard)?;
+        match shard.get_account(&address) {
+            Some(account) => BocRepr::encode_base64(account)
+                .map(Some)
+                .map_err(Into::into),
+            None => Ok(None),
+        }
+    })();
+
+    match res {
+        Ok(Some(boc)) => make_c_str(&boc),
+        Ok(None) => std::ptr::null_mut(),
+        Err(e) => {
+            log_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn local_shard_execute(
+    local_shard: *mut c_void,
+    transaction_emulator: *mut c_void,
+    message_boc: *const c_char,
+) -> *mut c_char {
+    ffi_run_with_response(|| {
+        let shard = ffi_cast_mut::<LocalShard>(local_shard)?;
+        let emulator = ffi_cast_mut::<TxEmulatorExt>(transaction_emulator)?;
+        let message = parse_boc(message_boc).context("Failed to deserialize message")?;
+
+        emulator.execute_in_shard(shard, message)
     })
 }
 
@@ -282,6 +524,32 @@ pub unsafe extern "C" fn tvm_emulator_destroy(tvm_emulator: *mut c_void) {
     ffi_drop::<TvmEmulator>(tvm_emulator);
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_snapshot(tvm_emulator: *mut c_void) -> *mut c_void {
+    ffi_new::<TvmEmulatorSnapshot, _>(|| {
+        let emulator = ffi_cast::<TvmEmulator>(tvm_emulator)?;
+        Ok(Box::new(emulator.snapshot()))
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_restore(
+    tvm_emulator: *mut c_void,
+    snapshot: *mut c_void,
+) -> bool {
+    ffi_run(|| {
+        let snapshot = ffi_cast::<TvmEmulatorSnapshot>(snapshot)?;
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.restore(snapshot);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_snapshot_destroy(snapshot: *mut c_void) {
+    ffi_drop::<TvmEmulatorSnapshot>(snapshot);
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn tvm_emulator_set_libraries(
     tvm_emulator: *mut c_void,
@@ -322,11 +590,22 @@ pub unsafe extern "C" fn tvm_emulator_set_c7(
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn tvm_emulator_set_extra_currencies(
-    _tvm_emulator: *mut c_void,
-    _extra_currencies: *const c_char,
+    tvm_emulator: *mut c_void,
+    extra_currencies: *const c_char,
 ) -> bool {
-    // TODO: implement
-    false
+    ffi_run(|| {
+        let extra = if extra_currencies.is_null() {
+            ExtraCurrencyCollection::new()
+        } else {
+            parse_extra_currencies(parse_str(extra_currencies)?)
+                .context("Failed to parse extra currencies")
+                .with_kind(ErrorKind::InvalidInput)?
+        };
+
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.args.extra = extra;
+        Ok(())
+    })
 }
 
 #[unsafe(no_mangle)]
@@ -354,16 +633,7 @@ pub unsafe extern "C" fn tvm_emulator_set_prev_blocks_info(
         }
 
         let info_cell = parse_boc(info_boc).context("Failed to deserialize previous blocks boc")?;
-        let info_value = Stack::load_stack_value_from_cell(info_cell.as_ref())
-            .context("Failed to deserialize previous blocks tuple")?;
-
-        if info_value.is_null() {
-            emulator.args.prev_blocks_info = None;
-        } else if let Ok(tuple) = info_value.into_tuple() {
-            emulator.args.prev_blocks_info = Some(tuple);
-        } else {
-            anyhow::bail!("Failed to set previous blocks tuple: not a tuple");
-        }
+        emulator.args.prev_blocks_info = parse_prev_blocks_info(Some(&info_cell))?;
         Ok(())
     })
 }
@@ -392,6 +662,67 @@ pub unsafe extern "C" fn tvm_emulator_set_debug_enabled(
     })
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_set_return_c7(
+    tvm_emulator: *mut c_void,
+    return_c7: bool,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.args.return_c7 = return_c7;
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_set_trace(
+    tvm_emulator: *mut c_void,
+    trace_level: c_int,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.args.trace = TraceMode::from_level(trace_level);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_set_log_limits(
+    tvm_emulator: *mut c_void,
+    limits_json: *const c_char,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.args.log_limits = parse_log_limits(limits_json)?;
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_set_log_callback(
+    tvm_emulator: *mut c_void,
+    callback: Option<LogCallback>,
+    user_data: *mut c_void,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.log_sink = make_log_sink(callback, user_data);
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_set_global_version(
+    tvm_emulator: *mut c_void,
+    global_version: u32,
+) -> bool {
+    ffi_run(|| {
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        emulator.args.global_version = (global_version > 0).then_some(global_version);
+        Ok(())
+    })
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn tvm_emulator_run_get_method(
     tvm_emulator: *mut c_void,
@@ -399,28 +730,86 @@ pub unsafe extern "C" fn tvm_emulator_run_get_method(
     stack_boc: *const c_char,
 ) -> *mut c_char {
     ffi_run_with_response::<RunGetMethodResponse, _>(|| {
-        let stack_cell = parse_boc(stack_boc).context("Failed to deserialize stack cell")?;
-        let stack = stack_cell
-            .parse::<Stack>()
-            .context("Failed to deserialize stack")?;
+        run_get_method_impl(tvm_emulator, method_id, stack_boc, StackFormat::Boc)
+    })
+}
 
-        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_run_get_method_ext(
+    tvm_emulator: *mut c_void,
+    method: *const c_char,
+    stack_boc: *const c_char,
+) -> *mut c_char {
+    run_get_method_by_name(tvm_emulator, method, stack_boc, StackFormat::Boc)
+}
 
-        let subscriber = emulator.make_logger();
-        let vm_log = subscriber.state().clone();
-        let _tracing = tracing::subscriber::set_default(subscriber);
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_run_get_method_json(
+    tvm_emulator: *mut c_void,
+    method: *const c_char,
+    stack: *const c_char,
+) -> *mut c_char {
+    run_get_method_by_name(tvm_emulator, method, stack, StackFormat::Json)
+}
 
-        let res = emulator.run_get_method(method_id, stack);
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_run_get_method_on_account(
+    tvm_emulator: *mut c_void,
+    shard_account_boc: *const c_char,
+    method: *const c_char,
+    stack_boc: *const c_char,
+) -> *mut c_char {
+    ffi_run_get_method(method, |method_id| {
+        let account = parse_shard_account(shard_account_boc)?;
+        let stack = json_stack::parse_stack(parse_str(stack_boc)?)?;
+
+        let emulator = ffi_cast::<TvmEmulator>(tvm_emulator)?.for_account(&account)?;
+        RunGetMethodResponse::new(
+            emulator.run_get_method_logged(method_id, stack)?,
+            StackFormat::Boc,
+        )
+    })
+}
 
-        Ok(RunGetMethodResponse {
-            success: JsonBool,
-            stack: res.stack,
-            gas_used: res.gas_used,
-            vm_exit_code: res.exit_code,
-            vm_log,
-            missing_library: res.missing_library,
-            debug_log: res.debug_log,
-        })
+unsafe fn run_get_method_by_name(
+    tvm_emulator: *mut c_void,
+    method: *const c_char,
+    stack: *const c_char,
+    format: StackFormat,
+) -> *mut c_char {
+    ffi_run_get_method(method, |method_id| {
+        run_get_method_impl(tvm_emulator, method_id, stack, format)
+    })
+}
+
+unsafe fn run_get_method_impl(
+    tvm_emulator: *mut c_void,
+    method_id: i32,
+    stack: *const c_char,
+    format: StackFormat,
+) -> Result<RunGetMethodResponse> {
+    let stack = json_stack::parse_stack(parse_str(stack)?)?;
+
+    let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+    RunGetMethodResponse::new(emulator.run_get_method_logged(method_id, stack)?, format)
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_run_get_methods(
+    tvm_emulator: *mut c_void,
+    calls_json: *const c_char,
+) -> *mut c_char {
+    ffi_run_with_response::<Vec<GetMethodCallResponse>, _>(|| {
+        let calls_json = parse_str(calls_json)?;
+        let calls = serde_json::from_str::<Vec<GetMethodCall>>(calls_json)
+            .context("Failed to parse get-method calls")
+            .with_kind(ErrorKind::InvalidInput)?;
+
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+        Ok(calls
+            .into_iter()
+            .map(|call| call.run(emulator, StackFormat::Boc))
+            .collect())
     })
 }
 
@@ -432,51 +821,85 @@ pub unsafe extern "C" fn tvm_emulator_emulate_run_method(
 ) -> *mut c_char {
     ffi_run_with_boc(|| {
         let params_boc = std::slice::from_raw_parts(params_boc.cast::<u8>(), len as _);
-        let params_cell = Boc::decode(params_boc)?;
+        emulate_run_method_impl(params_boc, gas_limit, None).map(|(res, _)| res)
+    })
+}
 
-        let mut cs = params_cell.as_slice()?;
-        let code = cs.load_reference_cloned()?;
-        let data = cs.load_reference_cloned()?;
+/// Runs a get-method from the raw params BOC.
+///
+/// VM log and debug output are collected only when `log_verbosity` is specified.
+fn emulate_run_method_impl(
+    params_boc: &[u8],
+    gas_limit: i64,
+    log_verbosity: Option<i32>,
+) -> Result<(Cell, String)> {
+    let params_cell = Boc::decode(params_boc)?;
+
+    let mut cs = params_cell.as_slice()?;
+    let code = cs.load_reference_cloned()?;
+    let data = cs.load_reference_cloned()?;
+
+    let mut stack_cs = cs.load_reference_as_slice()?;
+
+    let mut params = cs.load_reference_as_slice()?;
+    let mut c7_cs = params.load_reference_as_slice()?;
+    let libs = if params.has_remaining(0, 1) {
+        Some(params.load_reference_cloned()?)
+    } else {
+        None
+    };
 
-        let mut stack_cs = cs.load_reference_as_slice()?;
+    let method_id = cs.load_u32()? as i32;
 
-        let mut params = cs.load_reference_as_slice()?;
-        let mut c7_cs = params.load_reference_as_slice()?;
-        let libs = if params.has_remaining(0, 1) {
-            Some(params.load_reference_cloned()?)
-        } else {
-            None
-        };
+    let stack = Stack::load_from(&mut stack_cs)?;
+    let c7 = Stack::load_from(&mut c7_cs)?;
 
-        let method_id = cs.load_u32()? as i32;
+    let mut emulator = TvmEmulator::new(
+        code,
+        data,
+        log_verbosity.unwrap_or(DEFAULT_VM_LOG_VERBOSITY),
+    );
+
+    emulator.set_gas_limit(gas_limit as u64);
+    emulator.args.raw_c7 = Some(c7.items.try_get_owned::<Tuple>(0)?);
+    if libs.is_some() {
+        emulator.args.libraries = Some(Dict::from_raw(libs));
+    }
 
-        let stack = Stack::load_from(&mut stack_cs)?;
-        let c7 = Stack::load_from(&mut c7_cs)?;
+    let (res, log) = match log_verbosity {
+        None => (emulator.run_get_method(method_id, stack)?, String::new()),
+        Some(_) => {
+            emulator.args.debug_enabled = true;
 
-        let res = {
-            let mut emulator = TvmEmulator::new(code, data, 3);
+            let subscriber = emulator.make_logger();
+            let vm_log = subscriber.state().clone();
+            let _tracing = tracing::subscriber::set_default(subscriber);
 
-            emulator.set_gas_limit(gas_limit as u64);
-            emulator.args.raw_c7 = Some(c7.items.try_get_owned::<Tuple>(0)?);
-            if libs.is_some() {
-                emulator.args.libraries = Some(Dict::from_raw(libs));
+            let res = emulator.run_get_method(method_id, stack)?;
+
+            let mut log = vm_log.to_string();
+            if !res.debug_log.is_empty() {
+                if !log.is_empty() && !log.ends_with('\n') {
+                    log.push('\n');
+                }
+                log.push_str(&res.debug_log);
             }
-            emulator.run_get_method(method_id, stack)
-        };
+            (res, log)
+        }
+    };
 
-        CellBuilder::build_from((
-            res.exit_code as u32,
-            res.gas_used,
-            CellBuilder::build_from(res.stack)?,
-        ))
-        .map_err(Into::into)
-    })
+    let res = CellBuilder::build_from((
+        res.exit_code as u32,
+        res.gas_used,
+        CellBuilder::build_from(res.stack)?,
+    ))?;
+
+    Ok((res, log))
 }
 
 #[repr(C)]
 pub struct TvmEulatorEmulateRunMethodResponse {
     pub response: *mut c_char,
-    // NOTE: Not really set even in native impl due to verbosity level 0.
     pub log: *mut c_char,
 }
 
@@ -486,7 +909,28 @@ pub unsafe extern "C" fn tvm_emulator_emulate_run_method_detailed(
     params_boc: *const c_char,
     gas_limit: i64,
 ) -> *mut c_void {
-    let response = tvm_emulator_emulate_run_method(len, params_boc, gas_limit);
+    tvm_emulator_emulate_run_method_detailed_ext(
+        len,
+        params_boc,
+        gas_limit,
+        DEFAULT_VM_LOG_VERBOSITY,
+    )
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_emulate_run_method_detailed_ext(
+    len: u32,
+    params_boc: *const c_char,
+    gas_limit: i64,
+    vm_log_verbosity: c_int,
+) -> *mut c_void {
+    let mut log = String::new();
+    let response = ffi_run_with_boc(|| {
+        let params_boc = std::slice::from_raw_parts(params_boc.cast::<u8>(), len as _);
+        let (res, vm_log) = emulate_run_method_impl(params_boc, gas_limit, Some(vm_log_verbosity))?;
+        log = vm_log;
+        Ok(res)
+    });
     if response.is_null() {
         return std::ptr::null_mut();
     }
@@ -495,7 +939,7 @@ pub unsafe extern "C" fn tvm_emulator_emulate_run_method_detailed(
 
     let res_ptr = res.as_mut_ptr();
     (&raw mut (*res_ptr).response).write(response);
-    (&raw mut (*res_ptr).log).write(make_c_str(""));
+    (&raw mut (*res_ptr).log).write(make_c_str(&log));
 
     Box::into_raw(res).cast()
 }
@@ -534,19 +978,9 @@ pub unsafe extern "C" fn tvm_emulator_send_external_message(
         let vm_log = subscriber.state().clone();
         let _tracing = tracing::subscriber::set_default(subscriber);
 
-        let res = emulator.send_external_message(message_body_cell);
+        let res = emulator.send_external_message(message_body_cell)?;
 
-        Ok(TvmEmulatorSendMessageResponse {
-            success: JsonBool,
-            gas_used: res.gas_used,
-            vm_exit_code: res.exit_code,
-            accepted: res.accepted,
-            vm_log,
-            missing_library: res.missing_library,
-            actions: res.actions,
-            new_code: res.code,
-            new_data: res.data,
-        })
+        TvmEmulatorSendMessageResponse::new(res, vm_log)
     })
 }
 
@@ -566,19 +1000,41 @@ pub unsafe extern "C" fn tvm_emulator_send_internal_message(
         let vm_log = subscriber.state().clone();
         let _tracing = tracing::subscriber::set_default(subscriber);
 
-        let res = emulator.send_internal_message(message_body_cell, amount);
+        let res = emulator.send_internal_message(message_body_cell, amount)?;
 
-        Ok(TvmEmulatorSendMessageResponse {
-            success: JsonBool,
-            gas_used: res.gas_used,
-            vm_exit_code: res.exit_code,
-            accepted: res.accepted,
-            vm_log,
-            missing_library: res.missing_library,
-            actions: res.actions,
-            new_code: res.code,
-            new_data: res.data,
-        })
+        TvmEmulatorSendMessageResponse::new(res, vm_log)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tvm_emulator_send_internal_message_ext(
+    tvm_emulator: *mut c_void,
+    message: *const c_char,
+) -> *mut c_char {
+    ffi_run_with_response::<TvmEmulatorSendMessageResponse, _>(|| {
+        let message = parse_str(message)?.trim();
+
+        let emulator = ffi_cast_mut::<TvmEmulator>(tvm_emulator)?;
+
+        let message = if message.starts_with('{') {
+            serde_json::from_str::<InternalMessageParams>(message)
+                .context("Failed to parse message params")
+                .with_kind(ErrorKind::InvalidInput)?
+                .build(emulator.args.address())
+                .context("Failed to build message")?
+        } else {
+            Boc::decode_base64(message)
+                .context("Failed to parse message boc")
+                .with_kind(ErrorKind::InvalidInput)?
+        };
+
+        let subscriber = emulator.make_logger();
+        let vm_log = subscriber.state().clone();
+        let _tracing = tracing::subscriber::set_default(subscriber);
+
+        let res = emulator.send_internal_message_full(message)?;
+
+        TvmEmulatorSendMessageResponse::new(res, vm_log)
     })
 }
 
@@ -589,159 +1045,163 @@ struct TxEmulatorExt {
     block_unixtime: u32,
     lt: u64,
     libraries: Dict<HashBytes, LibDescr>,
-    prev_blocks_info: Option<SafeRc<Tuple>>,
+    prev_blocks_info: Option<Cell>,
     debug_enabled: bool,
+    data_cells_diff: bool,
+    return_c7: bool,
+    trace: TraceMode,
+    /// Move `lt` (and optionally `block_unixtime`) past each committed transaction.
+    auto_advance: bool,
+    unixtime_step: u32,
+}
+
+/// Overrides of the emulator settings for a single emulation.
+#[derive(Default, serde::Deserialize)]
+struct EmulationCallParams {
+    #[serde(default)]
+    log_limits: Option<LogLimits>,
+    #[serde(default)]
+    vm_log_verbosity: Option<i32>,
+    #[serde(default)]
+    vm_log_mask: Option<u32>,
 }
 
 impl TxEmulatorExt {
     fn rebuild_executor(&mut self, config: &tvm_emulator::ParsedConfig) -> Result<()> {
-        let new = TxEmulator::new(config.params.clone(), self.base.verbosity)?;
+        // NOTE: Only config-derived fields are taken from the new emulator.
+        let new = TxEmulator::new(config.params.clone(), 0)?;
         self.base.config = new.config;
         self.base.vm_modifiers.signature_with_id = new.vm_modifiers.signature_with_id;
         Ok(())
     }
 
-    fn make_params(&self) -> tycho_executor::ExecutorParams {
-        tycho_executor::ExecutorParams {
-            libraries: self.libraries.clone(),
-            rand_seed: self.base.rand_seed,
+    fn make_params(&self) -> Result<EmulationParams> {
+        Ok(EmulationParams {
             block_unixtime: self.block_unixtime,
             block_lt: self.lt,
-            vm_modifiers: self.base.vm_modifiers,
+            libraries: self.libraries.clone(),
+            prev_blocks_info: parse_prev_blocks_info(self.prev_blocks_info.as_ref())?,
+            ignore_chksig: self.base.vm_modifiers.chksig_always_succeed,
+            debug_enabled: self.debug_enabled,
             disable_delete_frozen_accounts: true,
             charge_action_fees_on_fail: true,
             full_body_in_bounced: false,
             strict_extra_currency: true,
             authority_marks_enabled: true,
-            // Will be overwritten
-            prev_mc_block_id: None,
-        }
+            data_cells_diff: self.data_cells_diff,
+            return_c7: self.return_c7,
+            trace: self.trace,
+            log_limits: None,
+            vm_log_mask: None,
+        })
     }
 
     fn emulate(
         &mut self,
         account: ShardAccount,
-        input: TxEmulatorInput,
+        input: EmulationInput,
+        call: &EmulationCallParams,
     ) -> Result<TxEmulatorResponse> {
-        let is_external;
-        let is_tock;
-        let message = match input {
-            TxEmulatorInput::Ordinary(msg) => {
-                let msg_info = msg
-                    .parse::<MsgInfo>()
-                    .context("Failed to unpack message info")?;
-
-                is_external = msg_info.is_external_in();
-                is_tock = false;
-                Some((msg, msg_info))
-            }
-            TxEmulatorInput::TickTock { is_tock: input } => {
-                is_external = false;
-                is_tock = input;
-                None
-            }
-        };
-
-        let IntAddr::Std(address) =
-            (match account.load_account().context("Failed to unpack account")? {
-                Some(account) => account.address,
-                None => match message.as_ref().map(|(_, info)| info) {
-                    Some(MsgInfo::Int(info)) => info.dst.clone(),
-                    Some(MsgInfo::ExtIn(info)) => info.dst.clone(),
-                    Some(MsgInfo::ExtOut(_)) => {
-                        anyhow::bail!("Only internal and external inbound messages are accepted");
-                    }
-                    None => anyhow::bail!("Can't run tick-tock transaction on account_none"),
-                },
-            })
-        else {
-            anyhow::bail!("var_addr is not supported");
-        };
+        let mut params = self.make_params()?;
+        params.log_limits = call.log_limits;
+        params.vm_log_mask = resolve_vm_log_mask(call.vm_log_mask, call.vm_log_verbosity);
+        let outcome = self.base.emulate(&account, input, &params)?;
+        if let EmulationOutcome::Committed(tx) = &outcome {
+            self.advance([tx])?;
+        }
+        TxEmulatorResponse::try_from(outcome)
+    }
 
-        let debug_enabled = self.debug_enabled;
-        let mut params = self.make_params();
-        if params.block_unixtime == 0 {
-            params.block_unixtime = now_sec_u64() as u32
+    /// Emulates independent jobs with the same params.
+    fn emulate_batch(
+        &mut self,
+        jobs: Vec<EmulationJob>,
+        threads: u32,
+    ) -> Result<Vec<TxEmulatorResponse>> {
+        let res = if threads > 1 {
+            self.base
+                .emulate_batch_parallel(&jobs, threads as usize, || self.make_params())?
+        } else {
+            let params = self.make_params()?;
+            self.base.emulate_batch(jobs, &params)
         };
 
-        self.base
-            .config
-            .update_storage_prices(params.block_unixtime)
-            .context("Failed to unpack storage prices")?;
+        self.advance(res.iter().filter_map(|res| match res {
+            Ok(EmulationOutcome::Committed(tx)) => Some(tx),
+            _ => None,
+        }))?;
 
-        let mut debug_log = String::new();
-        let mut prev_blocks_info = self.prev_blocks_info.clone();
-        let mut smc_info_hook = move |smc_info: &mut tycho_executor::phase::ComputePhaseSmcInfo| {
-            smc_info.base.base.prev_blocks_info = prev_blocks_info.take();
-            Ok(())
-        };
-        let mut inspector = tycho_executor::ExecutorInspector {
-            debug: debug_enabled.then_some(&mut debug_log),
-            modify_smc_info: Some(&mut smc_info_hook),
-            ..Default::default()
-        };
+        Ok(res
+            .into_iter()
+            .map(TxEmulatorResponse::from_result)
+            .collect::<Vec<_>>())
+    }
 
-        let subscriber = self.base.make_logger();
-        let vm_log = subscriber.state().clone();
-        let _tracing = tracing::subscriber::set_default(subscriber);
+    fn execute_in_shard(
+        &mut self,
+        shard: &mut LocalShard,
+        message: Cell,
+    ) -> Result<LocalShardResponse> {
+        let params = self.make_params()?;
+        let outcome = shard.execute(&mut self.base, message, &params)?;
+        if let LocalShardOutcome::Committed(tree) = &outcome {
+            self.advance(tree.transactions.iter().map(|node| &node.tx))?;
+        }
+        LocalShardResponse::try_from(outcome)
+    }
 
-        let output = match message {
-            Some((msg_root, _)) => tycho_executor::Executor::new(&params, &self.base.config)
-                .with_min_lt(params.block_lt)
-                .begin_ordinary_ext(
-                    &address,
-                    is_external,
-                    msg_root,
-                    &account,
-                    Some(&mut inspector),
-                ),
-            None => {
-                let ty = if is_tock {
-                    TickTock::Tock
-                } else {
-                    TickTock::Tick
-                };
-                tycho_executor::Executor::new(&params, &self.base.config)
-                    .with_min_lt(params.block_lt)
-                    .begin_tick_tock_ext(&address, ty, &account, Some(&mut inspector))
-            }
-        };
+    /// Moves `lt` past all committed transactions and `block_unixtime`
+    /// past the latest of them (if auto-advance is enabled).
+    fn advance<'a, I>(&mut self, txs: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a EmulatedTransaction>,
+    {
+        if !self.auto_advance {
+            return Ok(());
+        }
 
-        let res = 'res: {
-            let output = match output {
-                Ok(uncommitted) => uncommitted
-                    .commit()
-                    .context("Failed to commit transaction")?,
-                Err(tycho_executor::TxError::Skipped) if is_external => {
-                    break 'res TxEmulatorResponse::NotAccepted(TxEmulatorMsgNotAcceptedResponse {
-                        success: JsonBool,
-                        error: "External message not accepted by smart contract",
-                        external_not_accepted: JsonBool,
-                        vm_log,
-                        vm_exit_code: inspector.exit_code.unwrap_or(0),
-                        debug_log,
-                    });
-                }
-                Err(e) => anyhow::bail!("Fatal executor error: {e:?}"),
-            };
+        let mut last_now = None;
+        for tx in txs {
+            let tx = tx
+                .transaction
+                .load()
+                .context("Failed to unpack transaction")?;
 
-            TxEmulatorResponse::Success(TxEmulatorSuccessResponse {
-                success: JsonBool,
-                transaction: output.transaction.into_inner(),
-                shard_account: output.new_state,
-                vm_log,
-                actions: inspector.actions,
-                debug_log,
-            })
-        };
+            let end_lt = tx.lt + 1 + tx.out_msg_count.into_inner() as u64;
+            self.lt = self.lt.max(end_lt);
+            last_now = last_now.max(Some(tx.now));
+        }
 
-        Ok(res)
+        if self.unixtime_step > 0
+            && let Some(now) = last_now
+        {
+            self.block_unixtime = now.saturating_add(self.unixtime_step);
+        }
+        Ok(())
     }
 }
 
-enum TxEmulatorInput {
-    Ordinary(Cell),
-    TickTock { is_tock: bool },
+/// `(user_data, line, len)`. The line is not null-terminated.
+type LogCallback = unsafe extern "C" fn(*mut c_void, *const c_char, usize);
+
+fn make_log_sink(callback: Option<LogCallback>, user_data: *mut c_void) -> Option<LogSink> {
+    struct UserData(*mut c_void);
+
+    impl UserData {
+        fn get(&self) -> *mut c_void {
+            self.0
+        }
+    }
+
+    // SAFETY: The host is responsible for `user_data` being usable from any thread.
+    unsafe impl Send for UserData {}
+    unsafe impl Sync for UserData {}
+
+    let callback = callback?;
+    let user_data = UserData(user_data);
+    Some(Arc::new(move |row: &str| unsafe {
+        callback(user_data.get(), row.as_ptr().cast(), row.len());
+    }))
 }
 
 fn log_error<T: std::fmt::Debug>(e: T) {
@@ -783,15 +1243,43 @@ where
     F: FnOnce() -> Result<T>,
     T: serde::Serialize,
 {
+    make_response(f(), None)
+}
+
+/// Resolves the get-method id from its name or number and runs `f` with it.
+///
+/// Error response carries the method id once it is resolved.
+unsafe fn ffi_run_get_method<F>(method: *const c_char, f: F) -> *mut c_char
+where
+    F: FnOnce(i32) -> Result<RunGetMethodResponse>,
+{
+    let method_id = (|| {
+        let method = parse_str(method)?;
+        Ok(method.parse::<MethodId>()?.resolve())
+    })();
+    let resolved_method_id = method_id.as_ref().ok().copied();
+
+    make_response(method_id.and_then(f), resolved_method_id)
+}
+
+fn make_response<T: serde::Serialize>(res: Result<T>, method_id: Option<i32>) -> *mut c_char {
     let response = 'res: {
-        let error = match f() {
+        let (error, error_kind) = match res {
             Ok(res) => match serde_json::to_string(&res) {
                 Ok(res) => break 'res res,
-                Err(e) => format!("Failed to serialize response: {e}"),
+                Err(e) => (
+                    format!("Failed to serialize response: {e}"),
+                    ErrorKind::Internal,
+                ),
             },
-            Err(e) => e.to_string(),
+            Err(e) => (e.to_string(), ErrorKind::of(&e)),
         };
-        serde_json::to_string(&TvmEmulatorErrorResponse { error: &error }).unwrap()
+        serde_json::to_string(&TvmEmulatorErrorResponse {
+            error: &error,
+            error_kind,
+            method_id,
+        })
+        .unwrap()
     };
 
     make_c_str(&response)
@@ -842,26 +1330,94 @@ unsafe fn ffi_cast<'a, T>(value: *mut c_void) -> Result<&'a T> {
     value.cast::<T>().as_ref().context("Object pointer is null")
 }
 
+unsafe fn parse_str<'a>(str: *const c_char) -> Result<&'a str> {
+    if str.is_null() {
+        return Err(anyhow::anyhow!("String pointer is null")).with_kind(ErrorKind::InvalidInput);
+    }
+    CStr::from_ptr(str)
+        .to_str()
+        .with_kind(ErrorKind::InvalidInput)
+}
+
 unsafe fn parse_boc(boc_str: *const c_char) -> Result<Cell> {
-    anyhow::ensure!(!boc_str.is_null(), "String pointer is null");
-    let boc_str = CStr::from_ptr(boc_str).to_str()?;
-    Boc::decode_base64(boc_str).map_err(Into::into)
+    let boc_str = parse_str(boc_str)?;
+    Boc::decode_base64(boc_str).with_kind(ErrorKind::InvalidInput)
+}
+
+unsafe fn parse_shard_account(boc_str: *const c_char) -> Result<ShardAccount> {
+    parse_boc(boc_str)
+        .context("Failed to deserialize shard account")?
+        .parse::<ShardAccount>()
+        .context("Failed to unpack shard account")
+        .with_kind(ErrorKind::InvalidInput)
 }
 
 unsafe fn parse_config(boc_str: *const c_char) -> Result<tvm_emulator::ParsedConfig> {
-    parse_boc(boc_str).and_then(tvm_emulator::ParsedConfig::try_from_root)
+    parse_boc(boc_str)
+        .with_kind(ErrorKind::ConfigError)
+        .and_then(tvm_emulator::ParsedConfig::try_from_root)
+}
+
+unsafe fn parse_log_limits(limits_json: *const c_char) -> Result<LogLimits> {
+    if limits_json.is_null() {
+        return Ok(LogLimits::default());
+    }
+    let limits_json = parse_str(limits_json)?;
+    serde_json::from_str(limits_json)
+        .context("Failed to parse log limits")
+        .with_kind(ErrorKind::InvalidInput)
+}
+
+unsafe fn parse_call_params(params_json: *const c_char) -> Result<EmulationCallParams> {
+    if params_json.is_null() {
+        return Ok(EmulationCallParams::default());
+    }
+    let params_json = parse_str(params_json)?;
+    serde_json::from_str(params_json)
+        .context("Failed to parse emulation params")
+        .with_kind(ErrorKind::InvalidInput)
 }
 
 unsafe fn parse_std_addr(addr_str: *const c_char) -> Result<StdAddr> {
-    anyhow::ensure!(!addr_str.is_null(), "String pointer is null");
-    let addr_str = CStr::from_ptr(addr_str).to_str()?;
-    addr_str.parse::<StdAddr>().map_err(Into::into)
+    let addr_str = parse_str(addr_str)?;
+    addr_str
+        .parse::<StdAddr>()
+        .with_kind(ErrorKind::InvalidInput)
+}
+
+/// Parses extra currencies either in the reference `id1=balance1 id2=balance2` format
+/// or as a JSON object.
+fn parse_extra_currencies(s: &str) -> Result<ExtraCurrencyCollection> {
+    let s = s.trim();
+    if s.starts_with('{') || s == "null" {
+        let mut de = serde_json::Deserializer::from_str(s);
+        let extra = serde_extra_currencies::deserialize(&mut de)?;
+        de.end()?;
+        return Ok(extra);
+    }
+
+    let mut items = std::collections::BTreeMap::new();
+    for item in s.split_whitespace() {
+        let (id, balance) = item
+            .split_once('=')
+            .with_context(|| format!("Expected `id=balance`, got `{item}`"))?;
+        let id = id
+            .parse::<u32>()
+            .with_context(|| format!("Invalid currency id `{id}`"))?;
+        let balance = balance
+            .parse::<VarUint248>()
+            .with_context(|| format!("Invalid balance of currency {id}"))?;
+        items.insert(id, balance);
+    }
+
+    Ok(ExtraCurrencyCollection::try_from(items)?)
 }
 
 unsafe fn parse_hash(hash_str: *const c_char) -> Result<HashBytes> {
-    anyhow::ensure!(!hash_str.is_null(), "String pointer is null");
-    let hash_str = CStr::from_ptr(hash_str).to_str()?;
-    hash_str.parse::<HashBytes>().map_err(Into::into)
+    let hash_str = parse_str(hash_str)?;
+    hash_str
+        .parse::<HashBytes>()
+        .with_kind(ErrorKind::InvalidInput)
 }
 
 /// Allocates a new C-string with `malloc`.
@@ -890,9 +1446,13 @@ fn make_c_str(str: &str) -> *mut c_char {
 
 #[cfg(test)]
 mod test {
+    use tycho_types::models::Transaction;
     use tycho_vm::{SmcInfo, SmcInfoBase};
 
     use super::*;
+    use crate::subscriber::VmLogDropped;
+    use crate::test_utils::{NOW, addr, config_root, int_msg, make_account};
+    use crate::util::now_sec_u64;
 
     #[test]
     fn tvm_run_get_method() -> anyhow::Result<()> {
@@ -951,4 +1511,277 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn tvm_run_get_method_detailed() -> anyhow::Result<()> {
+        // PUSHINT 1, DROP
+        let code = CellBuilder::build_from(0x7130u16)?;
+
+        let smc_info = SmcInfoBase::new()
+            .with_now(NOW)
+            .with_block_lt(0)
+            .with_tx_lt(0)
+            .with_account_addr(addr(1).into())
+            .require_ton_v4()
+            .with_code(code.clone())
+            .build_c7();
+        let c7 = tycho_vm::Stack::with_items(vec![smc_info.into_dyn_value()]);
+
+        let params = CellBuilder::build_from((
+            0u32,
+            code,
+            Cell::empty_cell(),
+            CellBuilder::build_from(tycho_vm::Stack::default())?,
+            CellBuilder::build_from((CellBuilder::build_from(c7)?, ()))?,
+        ))
+        .map(Boc::encode)?;
+
+        let res = unsafe {
+            tvm_emulator_emulate_run_method_detailed(
+                params.len() as u32,
+                params.as_ptr().cast(),
+                100000,
+            )
+        };
+        assert!(!res.is_null());
+
+        let res = unsafe { Box::from_raw(res.cast::<TvmEulatorEmulateRunMethodResponse>()) };
+        assert!(!res.response.is_null());
+        assert!(!res.log.is_null());
+
+        let log = unsafe { std::ffi::CStr::from_ptr(res.log) }
+            .to_str()?
+            .to_owned();
+        unsafe { run_method_detailed_result_destroy(Box::into_raw(res).cast()) };
+
+        assert!(log.contains("execute "));
+        assert!(log.contains("gas remaining: "));
+        Ok(())
+    }
+
+    #[test]
+    fn run_get_method_on_account_error() -> anyhow::Result<()> {
+        let code = CellBuilder::build_from(0xdeadbeefu32)?;
+        let emulator = TvmEmulator::new(code.clone(), Cell::empty_cell(), 0);
+        let emulator = Box::into_raw(Box::new(emulator)).cast::<c_void>();
+
+        let account = BocRepr::encode_base64(crate::local_shard::empty_shard_account()?)?;
+        let account = std::ffi::CString::new(account)?;
+        let method = std::ffi::CString::new("seqno")?;
+        let stack = Boc::encode_base64(CellBuilder::build_from(tycho_vm::Stack::default())?);
+        let stack = std::ffi::CString::new(stack)?;
+
+        let res = unsafe {
+            tvm_emulator_run_get_method_on_account(
+                emulator,
+                account.as_ptr(),
+                method.as_ptr(),
+                stack.as_ptr(),
+            )
+        };
+        let response = unsafe { CStr::from_ptr(res) }.to_str()?;
+        let response = serde_json::from_str::<serde_json::Value>(response)?;
+        unsafe { string_destroy(res) };
+
+        assert_eq!(response["success"], false);
+        assert_eq!(response["error_kind"], "invalid_input");
+        assert_eq!(response["method_id"], 85143);
+
+        let emulator = unsafe { Box::from_raw(emulator.cast::<TvmEmulator>()) };
+        assert_eq!(emulator.code, code);
+        assert!(emulator.args.address.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn extra_currencies_formats() -> anyhow::Result<()> {
+        let reference = parse_extra_currencies("1=100  2=200")?;
+        let json = parse_extra_currencies(r#"{ "1": "100", "2": "200" }"#)?;
+        assert_eq!(reference, json);
+
+        let items = reference.as_dict().iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            items,
+            [(1, VarUint248::new(100)), (2, VarUint248::new(200))]
+        );
+
+        assert_eq!(parse_extra_currencies("")?, ExtraCurrencyCollection::new());
+        assert_eq!(
+            parse_extra_currencies("null")?,
+            ExtraCurrencyCollection::new()
+        );
+
+        assert!(parse_extra_currencies(r#"{ "1": "100" } garbage"#).is_err());
+        assert!(parse_extra_currencies("1=abc").is_err());
+        assert!(parse_extra_currencies("1").is_err());
+        Ok(())
+    }
+
+    fn make_tx_emulator(verbosity: c_int) -> anyhow::Result<Box<TxEmulatorExt>> {
+        let config = std::ffi::CString::new(Boc::encode_base64(config_root()))?;
+        let emulator = unsafe { transaction_emulator_create(config.as_ptr(), verbosity) };
+        assert!(!emulator.is_null());
+        Ok(unsafe { Box::from_raw(emulator.cast::<TxEmulatorExt>()) })
+    }
+
+    fn committed_tx(res: TxEmulatorResponse) -> anyhow::Result<Transaction> {
+        let TxEmulatorResponse::Success(res) = res else {
+            panic!("transaction must be committed");
+        };
+        res.transaction.parse::<Transaction>().map_err(Into::into)
+    }
+
+    #[test]
+    fn auto_advance() -> anyhow::Result<()> {
+        const STEP: u32 = 10;
+
+        let mut emulator = make_tx_emulator(0)?;
+        assert!(unsafe {
+            transaction_emulator_set_auto_advance(
+                &mut *emulator as *mut TxEmulatorExt as _,
+                true,
+                STEP,
+            )
+        });
+        emulator.block_unixtime = NOW;
+        emulator.lt = 1000;
+
+        let account = crate::local_shard::empty_shard_account()?;
+
+        // Single emulations are chained.
+        let mut prev_lt = 0;
+        for i in 1..=2 {
+            let res = emulator.emulate(
+                account.clone(),
+                EmulationInput::Ordinary(int_msg(&addr(0xff), &addr(i), false, &[])),
+                &Default::default(),
+            )?;
+            let tx = committed_tx(res)?;
+            assert!(tx.lt > prev_lt);
+            assert!(emulator.lt > tx.lt);
+            assert_eq!(emulator.block_unixtime, tx.now + STEP);
+            prev_lt = tx.lt;
+        }
+
+        // Batch jobs share params which are then moved past all of them.
+        let (lt, unixtime) = (emulator.lt, emulator.block_unixtime);
+        let jobs = (3..=5)
+            .map(|i| EmulationJob {
+                account: account.clone(),
+                input: EmulationInput::Ordinary(int_msg(&addr(0xff), &addr(i), false, &[])),
+            })
+            .collect::<Vec<_>>();
+        for res in emulator.emulate_batch(jobs, 1)? {
+            let tx = committed_tx(res)?;
+            assert!(tx.lt >= lt);
+            assert!(emulator.lt > tx.lt);
+            assert_eq!(tx.now, unixtime);
+        }
+        assert_eq!(emulator.block_unixtime, unixtime + STEP);
+
+        // Local shard execution moves params past the whole tree.
+        let (lt, unixtime) = (emulator.lt, emulator.block_unixtime);
+        let mut shard = LocalShard::new();
+        let LocalShardResponse::Success(res) =
+            emulator.execute_in_shard(&mut shard, int_msg(&addr(0xff), &addr(6), false, &[]))?
+        else {
+            panic!("local shard execution must succeed");
+        };
+        for tx in res.transactions {
+            let tx = tx.transaction.parse::<Transaction>()?;
+            assert!(tx.lt >= lt);
+            assert!(emulator.lt > tx.lt);
+        }
+        assert_eq!(emulator.block_unixtime, unixtime + STEP);
+
+        // Nothing is moved when auto-advance is disabled.
+        emulator.auto_advance = false;
+        let (lt, unixtime) = (emulator.lt, emulator.block_unixtime);
+        let tx = committed_tx(emulator.emulate(
+            account,
+            EmulationInput::Ordinary(int_msg(&addr(0xff), &addr(7), false, &[])),
+            &Default::default(),
+        )?)?;
+        assert!(tx.lt >= lt);
+        assert_eq!(emulator.lt, lt);
+        assert_eq!(emulator.block_unixtime, unixtime);
+        Ok(())
+    }
+
+    /// Emulates an internal message to an account which drops all items
+    /// of the initial stack and returns its VM log.
+    fn emulate_vm_log(
+        emulator: &mut TxEmulatorExt,
+        call: &EmulationCallParams,
+    ) -> anyhow::Result<(String, VmLogDropped)> {
+        let account = make_account(&addr(1), &[0x30; 5]);
+        let msg = int_msg(&addr(0xff), &addr(1), false, &[]);
+
+        let res = emulator.emulate(account, EmulationInput::Ordinary(msg), call)?;
+        let TxEmulatorResponse::Success(res) = res else {
+            panic!("transaction must be committed");
+        };
+        Ok((res.vm_log.to_string(), res.vm_log_dropped))
+    }
+
+    #[test]
+    fn per_call_log_limits() -> anyhow::Result<()> {
+        let mut emulator = make_tx_emulator(1)?;
+
+        let (log, dropped) = emulate_vm_log(&mut emulator, &Default::default())?;
+        assert!(log.lines().count() > 2);
+        assert_eq!(dropped.rows, 0);
+
+        let call = serde_json::from_str::<EmulationCallParams>(
+            r#"{ "log_limits": { "max_rows": 2, "retention": "head" } }"#,
+        )?;
+        let (limited, dropped) = emulate_vm_log(&mut emulator, &call)?;
+        assert_eq!(
+            limited.lines().collect::<Vec<_>>(),
+            log.lines().take(2).collect::<Vec<_>>()
+        );
+        assert_eq!(dropped.rows as usize, log.lines().count() - 2);
+
+        // Per-call limits are not kept by the emulator.
+        let (log, dropped) = emulate_vm_log(&mut emulator, &Default::default())?;
+        assert!(log.lines().count() > 2);
+        assert_eq!(dropped.rows, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn per_call_vm_log_mask() -> anyhow::Result<()> {
+        let mut emulator = make_tx_emulator(0)?;
+        let call = |json: &str| serde_json::from_str::<EmulationCallParams>(json);
+
+        let (log, _) = emulate_vm_log(&mut emulator, &Default::default())?;
+        assert!(log.is_empty());
+
+        let (log, _) = emulate_vm_log(&mut emulator, &call(r#"{ "vm_log_verbosity": 1 }"#)?)?;
+        assert!(log.lines().any(|row| row == "execute DROP"));
+        assert!(!log.contains("stack"));
+
+        // Explicit mask takes precedence over verbosity.
+        let (log, _) = emulate_vm_log(
+            &mut emulator,
+            &call(r#"{ "vm_log_verbosity": 0, "vm_log_mask": 3 }"#)?,
+        )?;
+        assert!(log.lines().any(|row| row == "execute DROP"));
+        assert!(log.contains("stack"));
+
+        // Per-call mask is not kept by the emulator.
+        let (log, _) = emulate_vm_log(&mut emulator, &Default::default())?;
+        assert!(log.is_empty());
+
+        assert!(unsafe {
+            transaction_emulator_set_vm_log_mask(&mut *emulator as *mut TxEmulatorExt as _, 1)
+        });
+        let (log, _) = emulate_vm_log(&mut emulator, &Default::default())?;
+        assert!(log.lines().any(|row| row == "execute DROP"));
+
+        // Per-call verbosity also overrides the emulator mask.
+        let (log, _) = emulate_vm_log(&mut emulator, &call(r#"{ "vm_log_verbosity": 0 }"#)?)?;
+        assert!(log.is_empty());
+        Ok(())
+    }
 }
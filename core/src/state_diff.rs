@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use num_bigint::{BigInt, Sign};
+use serde::Serialize;
+use tycho_types::models::{
+    Account, AccountState, ExtraCurrencyCollection, ShardAccount, StateInit,
+};
+use tycho_types::prelude::*;
+
+use crate::util::{UniqueCells, serde_optional_string, serde_string};
+
+/// Summary of changes between two states of the same account.
+#[derive(Clone, Serialize)]
+pub struct AccountStateDiff {
+    /// `nonexist`, `uninit`, `active` or `frozen`.
+    pub status_before: &'static str,
+    pub status_after: &'static str,
+    #[serde(with = "serde_string")]
+    pub balance_before: u128,
+    #[serde(with = "serde_string")]
+    pub balance_after: u128,
+    #[serde(with = "serde_string")]
+    pub balance_delta: i128,
+    /// Non-zero deltas of extra currencies by id.
+    pub extra_currencies_delta: BTreeMap<u32, String>,
+    pub code_hash_before: Option<HashBytes>,
+    pub code_hash_after: Option<HashBytes>,
+    pub code_changed: bool,
+    pub data_hash_before: Option<HashBytes>,
+    pub data_hash_after: Option<HashBytes>,
+    pub data_changed: bool,
+    pub storage_before: Option<StorageStat>,
+    pub storage_after: Option<StorageStat>,
+    /// Cell-level diff of the persistent data (c4).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_cells: Option<CellsDiff>,
+}
+
+impl AccountStateDiff {
+    /// Compares two account states. Cell-level diff of the
+    /// persistent data is computed only when `with_data_cells` is set.
+    pub fn compute(
+        before: &ShardAccount,
+        after: &ShardAccount,
+        with_data_cells: bool,
+    ) -> Result<Self> {
+        let before = before
+            .load_account()
+            .context("Failed to unpack original account")?;
+        let after = after
+            .load_account()
+            .context("Failed to unpack new account")?;
+
+        let balance_before = balance(before.as_ref());
+        let balance_after = balance(after.as_ref());
+
+        let extra_currencies_delta = extra_currencies_delta(
+            before.as_ref().map(|x| &x.balance.other),
+            after.as_ref().map(|x| &x.balance.other),
+        )?;
+
+        let code_before = state_init_cell(before.as_ref(), |x| x.code.as_ref());
+        let code_after = state_init_cell(after.as_ref(), |x| x.code.as_ref());
+        let data_before = state_init_cell(before.as_ref(), |x| x.data.as_ref());
+        let data_after = state_init_cell(after.as_ref(), |x| x.data.as_ref());
+
+        let code_hash_before = code_before.map(|x| *x.repr_hash());
+        let code_hash_after = code_after.map(|x| *x.repr_hash());
+        let data_hash_before = data_before.map(|x| *x.repr_hash());
+        let data_hash_after = data_after.map(|x| *x.repr_hash());
+
+        let data_cells = with_data_cells.then(|| CellsDiff::compute(data_before, data_after));
+
+        Ok(Self {
+            status_before: status_name(before.as_ref()),
+            status_after: status_name(after.as_ref()),
+            balance_before,
+            balance_after,
+            balance_delta: balance_after as i128 - balance_before as i128,
+            extra_currencies_delta,
+            code_changed: code_hash_before != code_hash_after,
+            code_hash_before,
+            code_hash_after,
+            data_changed: data_hash_before != data_hash_after,
+            data_hash_before,
+            data_hash_after,
+            storage_before: before.as_ref().map(StorageStat::new),
+            storage_after: after.as_ref().map(StorageStat::new),
+            data_cells,
+        })
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct StorageStat {
+    #[serde(with = "serde_string")]
+    pub cells: u64,
+    #[serde(with = "serde_string")]
+    pub bits: u64,
+    pub last_paid: u32,
+    #[serde(with = "serde_optional_string")]
+    pub due_payment: Option<u128>,
+}
+
+impl StorageStat {
+    fn new(account: &Account) -> Self {
+        let info = &account.storage_stat;
+        Self {
+            cells: info.used.cells.into_inner(),
+            bits: info.used.bits.into_inner(),
+            last_paid: info.last_paid,
+            due_payment: info.due_payment.map(|x| x.into_inner()),
+        }
+    }
+}
+
+/// Unique cells which are present only in one of the trees.
+#[derive(Default, Clone, Serialize)]
+pub struct CellsDiff {
+    pub added: Vec<HashBytes>,
+    pub removed: Vec<HashBytes>,
+}
+
+impl CellsDiff {
+    pub fn compute(before: Option<&Cell>, after: Option<&Cell>) -> Self {
+        if before.map(|x| x.repr_hash()) == after.map(|x| x.repr_hash()) {
+            return Self::default();
+        }
+
+        let before = collect_unique_cells(before);
+        let after = collect_unique_cells(after);
+
+        let mut added = after
+            .iter()
+            .filter(|hash| !before.contains(*hash))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut removed = before
+            .iter()
+            .filter(|hash| !after.contains(*hash))
+            .copied()
+            .collect::<Vec<_>>();
+
+        added.sort_unstable();
+        removed.sort_unstable();
+
+        Self { added, removed }
+    }
+}
+
+fn collect_unique_cells(root: Option<&Cell>) -> ahash::HashSet<HashBytes> {
+    let mut unique = UniqueCells::default();
+    if let Some(root) = root {
+        unique.visit(root.as_ref());
+    }
+    unique.hashes
+}
+
+fn status_name(account: Option<&Account>) -> &'static str {
+    match account.map(|x| &x.state) {
+        None => "nonexist",
+        Some(AccountState::Uninit) => "uninit",
+        Some(AccountState::Active(_)) => "active",
+        Some(AccountState::Frozen(_)) => "frozen",
+    }
+}
+
+fn balance(account: Option<&Account>) -> u128 {
+    account
+        .map(|x| x.balance.tokens.into_inner())
+        .unwrap_or_default()
+}
+
+fn state_init_cell<'a, F>(account: Option<&'a Account>, f: F) -> Option<&'a Cell>
+where
+    F: FnOnce(&'a StateInit) -> Option<&'a Cell>,
+{
+    match &account?.state {
+        AccountState::Active(state_init) => f(state_init),
+        _ => None,
+    }
+}
+
+fn extra_currencies_delta(
+    before: Option<&ExtraCurrencyCollection>,
+    after: Option<&ExtraCurrencyCollection>,
+) -> Result<BTreeMap<u32, String>> {
+    fn collect(
+        items: Option<&ExtraCurrencyCollection>,
+        sign: i32,
+        result: &mut BTreeMap<u32, BigInt>,
+    ) -> Result<()> {
+        let Some(items) = items else {
+            return Ok(());
+        };
+        for item in items.as_dict().iter() {
+            let (id, value) = item.context("Invalid extra currencies dict")?;
+            let value = value.to_string().parse::<BigInt>()?;
+            *result.entry(id).or_default() += value * sign;
+        }
+        Ok(())
+    }
+
+    let mut result = BTreeMap::new();
+    collect(before, -1, &mut result)?;
+    collect(after, 1, &mut result)?;
+
+    Ok(result
+        .into_iter()
+        .filter(|(_, delta)| delta.sign() != Sign::NoSign)
+        .map(|(id, delta)| (id, delta.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use tycho_types::models::{CurrencyCollection, StdAddr};
+    use tycho_types::num::{Tokens, VarUint248};
+
+    use super::*;
+    use crate::util::make_shard_account;
+
+    fn make_account(balance: CurrencyCollection, state: AccountState) -> ShardAccount {
+        let address = StdAddr::new(0, HashBytes([0x11; 32]));
+        make_shard_account(address, balance, state, 0, 0).unwrap()
+    }
+
+    fn active_state(code: Cell, data: Cell) -> AccountState {
+        AccountState::Active(StateInit {
+            code: Some(code),
+            data: Some(data),
+            ..Default::default()
+        })
+    }
+
+    fn extra(items: &[(u32, u64)]) -> ExtraCurrencyCollection {
+        let items = items
+            .iter()
+            .map(|&(id, value)| (id, VarUint248::new(value as u128)))
+            .collect::<BTreeMap<_, _>>();
+        ExtraCurrencyCollection::try_from(items).unwrap()
+    }
+
+    fn cell_with_refs(value: u32, refs: &[Cell]) -> Cell {
+        let mut b = CellBuilder::new();
+        b.store_u32(value).unwrap();
+        for child in refs {
+            b.store_reference(child.clone()).unwrap();
+        }
+        b.build().unwrap()
+    }
+
+    #[test]
+    fn balance_delta() {
+        let state = active_state(Cell::empty_cell(), Cell::empty_cell());
+        let before = make_account(
+            CurrencyCollection {
+                tokens: Tokens::new(1000),
+                other: extra(&[(1, 100), (2, 50), (4, 5)]),
+            },
+            state.clone(),
+        );
+        let after = make_account(
+            CurrencyCollection {
+                tokens: Tokens::new(700),
+                other: extra(&[(1, 150), (3, 10), (4, 5)]),
+            },
+            state,
+        );
+
+        let diff = AccountStateDiff::compute(&before, &after, false).unwrap();
+        assert_eq!(diff.balance_before, 1000);
+        assert_eq!(diff.balance_after, 700);
+        assert_eq!(diff.balance_delta, -300);
+        assert_eq!(
+            diff.extra_currencies_delta,
+            BTreeMap::from([
+                (1, "50".to_owned()),
+                (2, "-50".to_owned()),
+                (3, "10".to_owned()),
+            ])
+        );
+        assert!(!diff.code_changed);
+        assert!(!diff.data_changed);
+        assert!(diff.data_cells.is_none());
+    }
+
+    #[test]
+    fn status_transitions() {
+        let balance = CurrencyCollection::new(1000);
+        let nonexist = crate::local_shard::empty_shard_account().unwrap();
+        let uninit = make_account(balance.clone(), AccountState::Uninit);
+        let active = make_account(
+            balance.clone(),
+            active_state(Cell::empty_cell(), Cell::empty_cell()),
+        );
+        let frozen = make_account(balance, AccountState::Frozen(HashBytes([0x22; 32])));
+
+        for (before, after, status_before, status_after) in [
+            (&nonexist, &uninit, "nonexist", "uninit"),
+            (&uninit, &active, "uninit", "active"),
+            (&active, &frozen, "active", "frozen"),
+            (&frozen, &nonexist, "frozen", "nonexist"),
+        ] {
+            let diff = AccountStateDiff::compute(before, after, false).unwrap();
+            assert_eq!(diff.status_before, status_before);
+            assert_eq!(diff.status_after, status_after);
+        }
+
+        let diff = AccountStateDiff::compute(&nonexist, &active, false).unwrap();
+        assert_eq!(diff.balance_delta, 1000);
+        assert!(diff.storage_before.is_none());
+        assert!(diff.storage_after.unwrap().cells > 0);
+        assert!(diff.code_changed);
+        assert_eq!(diff.code_hash_before, None);
+        assert_eq!(diff.code_hash_after, Some(*Cell::empty_cell().repr_hash()));
+    }
+
+    #[test]
+    fn code_and_data_change() {
+        let balance = CurrencyCollection::new(1000);
+        let code_a = cell_with_refs(1, &[]);
+        let code_b = cell_with_refs(2, &[]);
+        let data = cell_with_refs(3, &[]);
+
+        let before = make_account(balance.clone(), active_state(code_a.clone(), data.clone()));
+        let after = make_account(balance.clone(), active_state(code_b.clone(), data.clone()));
+
+        let diff = AccountStateDiff::compute(&before, &after, false).unwrap();
+        assert!(diff.code_changed);
+        assert_eq!(diff.code_hash_before, Some(*code_a.repr_hash()));
+        assert_eq!(diff.code_hash_after, Some(*code_b.repr_hash()));
+        assert!(!diff.data_changed);
+        assert_eq!(diff.data_hash_before, diff.data_hash_after);
+
+        let new_data = cell_with_refs(4, &[]);
+        let after = make_account(balance, active_state(code_a, new_data.clone()));
+
+        let diff = AccountStateDiff::compute(&before, &after, false).unwrap();
+        assert!(!diff.code_changed);
+        assert!(diff.data_changed);
+        assert_eq!(diff.data_hash_before, Some(*data.repr_hash()));
+        assert_eq!(diff.data_hash_after, Some(*new_data.repr_hash()));
+    }
+
+    #[test]
+    fn data_cells_diff() {
+        let shared = cell_with_refs(1, &[]);
+        let old_child = cell_with_refs(2, &[]);
+        let new_child = cell_with_refs(3, &[]);
+        let data_before = cell_with_refs(0, &[shared.clone(), old_child.clone()]);
+        let data_after = cell_with_refs(0, &[shared.clone(), new_child.clone()]);
+
+        let balance = CurrencyCollection::new(1000);
+        let before = make_account(
+            balance.clone(),
+            active_state(Cell::empty_cell(), data_before.clone()),
+        );
+        let after = make_account(
+            balance,
+            active_state(Cell::empty_cell(), data_after.clone()),
+        );
+
+        let diff = AccountStateDiff::compute(&before, &after, true).unwrap();
+        let cells = diff.data_cells.unwrap();
+
+        let mut added = vec![*data_after.repr_hash(), *new_child.repr_hash()];
+        let mut removed = vec![*data_before.repr_hash(), *old_child.repr_hash()];
+        added.sort_unstable();
+        removed.sort_unstable();
+        assert_eq!(cells.added, added);
+        assert_eq!(cells.removed, removed);
+
+        // Same data produces an empty diff.
+        let diff = AccountStateDiff::compute(&before, &before, true).unwrap();
+        let cells = diff.data_cells.unwrap();
+        assert!(cells.added.is_empty());
+        assert!(cells.removed.is_empty());
+
+        // All cells are added when there was no data.
+        let cells = CellsDiff::compute(None, Some(&data_before));
+        assert_eq!(cells.added.len(), 3);
+        assert!(cells.removed.is_empty());
+    }
+}
@@ -2,14 +2,23 @@ use std::collections::VecDeque;
 use std::num::NonZeroU64;
 use std::sync::{Arc, Mutex};
 
+use serde::{Deserialize, Serialize};
 use tracing::{Subscriber, span};
+use tycho_types::cell::HashBytes;
 use tycho_vm::VmLogMask;
 
 const VM_TARGET: &str = "tycho_vm";
 
+/// Max number of recorded trace steps, the rest are only counted.
+const MAX_TRACE_STEPS: usize = 100_000;
+
+/// Receives VM log rows as they are produced.
+pub type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
 pub struct VmLogSubscriber {
     vm_log_mask: VmLogMask,
     state: VmLogRows,
+    sink: Option<LogSink>,
 }
 
 impl VmLogSubscriber {
@@ -17,14 +26,39 @@ impl VmLogSubscriber {
         Self {
             vm_log_mask: mask,
             state: VmLogRows {
-                inner: Arc::new(Mutex::new(Inner {
-                    capacity,
-                    rows: VecDeque::with_capacity(capacity.min(256)),
-                })),
+                inner: Arc::new(Mutex::new(Inner::new(capacity))),
             },
+            sink: None,
         }
     }
 
+    /// Overrides the default capacity and retention policy.
+    pub fn with_limits(self, limits: LogLimits) -> Self {
+        self.state.inner.lock().unwrap().set_limits(limits);
+        self
+    }
+
+    /// Additionally records a structured trace.
+    pub fn with_trace(self, mode: TraceMode) -> Self {
+        if mode != TraceMode::Disabled {
+            self.state.inner.lock().unwrap().trace = Some(VmTrace {
+                with_stack: mode == TraceMode::WithStack,
+                max_steps: MAX_TRACE_STEPS,
+                steps: Vec::new(),
+                dropped_steps: 0,
+                last_gas_remaining: None,
+                pending: PendingStep::default(),
+            });
+        }
+        self
+    }
+
+    /// Streams rows to the sink instead of buffering them.
+    pub fn with_sink(mut self, sink: Option<LogSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
     pub fn state(&self) -> &VmLogRows {
         &self.state
     }
@@ -48,10 +82,19 @@ impl Subscriber for VmLogSubscriber {
             return;
         }
 
+        let mut streamed = Vec::new();
         event.record(&mut LogVisitor {
             inner: &mut self.state.inner.lock().unwrap(),
             mask: self.vm_log_mask,
+            streamed: self.sink.is_some().then_some(&mut streamed),
         });
+
+        // NOTE: The sink is called without the lock so that it can access the log.
+        if let Some(sink) = &self.sink {
+            for row in streamed {
+                sink(&row);
+            }
+        }
     }
 
     fn enter(&self, _: &span::Id) {}
@@ -62,10 +105,38 @@ impl Subscriber for VmLogSubscriber {
 struct LogVisitor<'a> {
     inner: &'a mut Inner,
     mask: VmLogMask,
+    streamed: Option<&'a mut Vec<String>>,
 }
 
 impl tracing::field::Visit for LogVisitor<'_> {
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        if field.name() == "gas_remaining"
+            && let Some(trace) = &mut self.inner.trace
+        {
+            trace.record_gas_remaining(value);
+        }
+        self.write_row(field, &value);
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        if field.name() == "gas_remaining"
+            && let Some(trace) = &mut self.inner.trace
+        {
+            trace.record_gas_remaining(i64::try_from(value).unwrap_or(i64::MAX));
+        }
+        self.write_row(field, &value);
+    }
+
     fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if let Some(trace) = &mut self.inner.trace {
+            trace.record(field.name(), value);
+        }
+        self.write_row(field, value);
+    }
+}
+
+impl LogVisitor<'_> {
+    fn write_row(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
         use std::fmt::Write;
 
         const STACK_MASK: VmLogMask = VmLogMask::DUMP_STACK.union(VmLogMask::DUMP_STACK_VERBOSE);
@@ -95,7 +166,10 @@ impl tracing::field::Visit for LogVisitor<'_> {
         };
 
         if res.is_ok() {
-            self.inner.rows.push_back(buffer);
+            match &mut self.streamed {
+                Some(streamed) => streamed.push(buffer),
+                None => self.inner.push(buffer),
+            }
         }
     }
 }
@@ -114,34 +188,460 @@ impl serde::Serialize for VmLogRows {
     }
 }
 
+impl VmLogRows {
+    /// Takes recorded trace steps (if trace was enabled).
+    ///
+    /// Steps over the limit are not recorded, see [`VmLogDropped::trace_steps`].
+    pub fn take_trace(&self) -> Option<Vec<VmTraceStep>> {
+        let mut inner = self.inner.lock().unwrap();
+        let trace = inner.trace.as_mut()?;
+        Some(std::mem::take(&mut trace.steps))
+    }
+
+    /// Rows and bytes dropped due to the log limits.
+    pub fn dropped(&self) -> VmLogDropped {
+        let inner = self.inner.lock().unwrap();
+        VmLogDropped {
+            trace_steps: inner.trace.as_ref().map_or(0, |trace| trace.dropped_steps),
+            ..inner.dropped
+        }
+    }
+}
+
 impl std::fmt::Display for VmLogRows {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut inner = self.inner.lock().unwrap();
-        for row in std::mem::take(&mut inner.rows) {
+        inner.head_bytes = 0;
+        inner.tail_bytes = 0;
+        inner.head_full = false;
+        let dropped = std::mem::take(&mut inner.dropped);
+
+        for row in std::mem::take(&mut inner.head) {
+            writeln!(f, "{row}")?;
+        }
+        if inner.retention == LogRetention::HeadTail && dropped.rows > 0 {
+            let VmLogDropped { rows, bytes, .. } = dropped;
+            writeln!(f, "... {rows} rows ({bytes} bytes) elided ...")?;
+        }
+        for row in std::mem::take(&mut inner.tail) {
             writeln!(f, "{row}")?;
         }
         Ok(())
     }
 }
 
+/// VM log retention policy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRetention {
+    /// Keep the first rows.
+    Head,
+    /// Keep the last rows.
+    #[default]
+    Tail,
+    /// Keep the first and the last rows (half of the limits each).
+    HeadTail,
+}
+
+/// VM log limits.
+///
+/// Unset limits are taken from the verbosity-based defaults.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct LogLimits {
+    #[serde(default)]
+    pub max_rows: Option<usize>,
+    /// Total size of the kept rows including newlines.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    #[serde(default)]
+    pub retention: LogRetention,
+}
+
+/// Rows and bytes (including newlines) dropped from the VM log.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VmLogDropped {
+    pub rows: u64,
+    pub bytes: u64,
+    /// Executed instructions which were not recorded to the trace.
+    pub trace_steps: u64,
+}
+
+#[derive(Default, Clone, Copy)]
+struct RowsLimit {
+    rows: usize,
+    bytes: usize,
+}
+
 #[derive(Default)]
 struct Inner {
     capacity: usize,
-    rows: VecDeque<String>,
+    retention: LogRetention,
+    head: Vec<String>,
+    head_bytes: usize,
+    head_limit: RowsLimit,
+    head_full: bool,
+    tail: VecDeque<String>,
+    tail_bytes: usize,
+    tail_limit: RowsLimit,
+    dropped: VmLogDropped,
+    spare: Option<String>,
+    trace: Option<VmTrace>,
 }
 
 impl Inner {
-    fn get_buffer(&mut self) -> String {
+    fn new(capacity: usize) -> Self {
+        let mut res = Self {
+            capacity,
+            tail: VecDeque::with_capacity(capacity.min(256)),
+            ..Default::default()
+        };
+        res.set_limits(LogLimits::default());
+        res
+    }
+
+    fn set_limits(&mut self, limits: LogLimits) {
+        let rows = limits.max_rows.unwrap_or(self.capacity);
+        let bytes = limits.max_bytes.unwrap_or(usize::MAX);
+
+        let (head, tail) = match limits.retention {
+            LogRetention::Head => ((rows, bytes), (0, 0)),
+            LogRetention::Tail => ((0, 0), (rows, bytes)),
+            LogRetention::HeadTail => ((rows / 2, bytes / 2), (rows - rows / 2, bytes - bytes / 2)),
+        };
+
+        self.retention = limits.retention;
+        self.head_limit = RowsLimit {
+            rows: head.0,
+            bytes: head.1,
+        };
+        self.tail_limit = RowsLimit {
+            rows: tail.0,
+            bytes: tail.1,
+        };
+    }
+
+    fn push(&mut self, row: String) {
+        let len = row.len() + 1;
+
+        if !self.head_full {
+            if self.head.len() < self.head_limit.rows
+                && self.head_bytes.saturating_add(len) <= self.head_limit.bytes
+            {
+                self.head_bytes += len;
+                self.head.push(row);
+                return;
+            }
+            // Keep only a contiguous prefix.
+            self.head_full = true;
+        }
+
+        if self.tail_limit.rows == 0 || len > self.tail_limit.bytes {
+            self.drop_row(row);
+            return;
+        }
+
+        self.tail_bytes += len;
+        self.tail.push_back(row);
+
+        while self.tail.len() > self.tail_limit.rows || self.tail_bytes > self.tail_limit.bytes {
+            let Some(row) = self.tail.pop_front() else {
+                break;
+            };
+            self.tail_bytes -= row.len() + 1;
+            self.drop_row(row);
+        }
+    }
+
+    fn drop_row(&mut self, mut row: String) {
         const OK_LEN: usize = 128;
 
-        if self.rows.len() >= self.capacity
-            && let Some(mut s) = self.rows.pop_front()
-            && s.len() <= OK_LEN
+        self.dropped.rows += 1;
+        self.dropped.bytes += row.len() as u64 + 1;
+
+        if row.len() <= OK_LEN {
+            row.clear();
+            self.spare = Some(row);
+        }
+    }
+
+    fn get_buffer(&mut self) -> String {
+        self.spare.take().unwrap_or_default()
+    }
+}
+
+/// Structured trace mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceMode {
+    #[default]
+    Disabled,
+    Enabled,
+    /// Also record a stack snapshot before each instruction.
+    WithStack,
+}
+
+impl TraceMode {
+    pub fn from_level(level: i32) -> Self {
+        match level {
+            i32::MIN..=0 => Self::Disabled,
+            1 => Self::Enabled,
+            _ => Self::WithStack,
+        }
+    }
+
+    /// VM log events required to build the trace.
+    pub fn vm_log_mask(self) -> VmLogMask {
+        let mask = VmLogMask::MESSAGE
+            .union(VmLogMask::EXEC_LOCATION)
+            .union(VmLogMask::GAS_REMAINING);
+        match self {
+            Self::Disabled => VmLogMask::empty(),
+            Self::Enabled => mask,
+            Self::WithStack => mask.union(VmLogMask::DUMP_STACK),
+        }
+    }
+}
+
+/// Executed instruction.
+#[derive(Debug, Clone, Serialize)]
+pub struct VmTraceStep {
+    /// Instruction mnemonic with its immediate args.
+    pub opcode: String,
+    /// Hash of the code cell.
+    pub code_hash: Option<HashBytes>,
+    /// Bit offset in the code cell.
+    pub offset: Option<u16>,
+    /// Gas remaining before the instruction.
+    pub gas_remaining: Option<i64>,
+    /// Gas consumed by the instruction.
+    ///
+    /// Unknown for the last step.
+    pub gas_consumed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
+}
+
+struct VmTrace {
+    with_stack: bool,
+    max_steps: usize,
+    steps: Vec<VmTraceStep>,
+    dropped_steps: u64,
+    /// Gas remaining before the last executed instruction.
+    last_gas_remaining: Option<i64>,
+    pending: PendingStep,
+}
+
+#[derive(Default)]
+struct PendingStep {
+    code_hash: Option<HashBytes>,
+    offset: Option<u16>,
+    gas_remaining: Option<i64>,
+    stack: Option<String>,
+}
+
+impl VmTrace {
+    fn record(&mut self, field: &str, value: &dyn std::fmt::Debug) {
+        match field {
+            "opcode" => {
+                let pending = std::mem::take(&mut self.pending);
+                self.last_gas_remaining = pending.gas_remaining;
+
+                if self.steps.len() >= self.max_steps {
+                    self.dropped_steps += 1;
+                    return;
+                }
+
+                self.steps.push(VmTraceStep {
+                    opcode: format!("{value:?}"),
+                    code_hash: pending.code_hash,
+                    offset: pending.offset,
+                    gas_remaining: pending.gas_remaining,
+                    gas_consumed: None,
+                    stack: pending.stack,
+                });
+            }
+            "exec_location" => {
+                let location = parse_exec_location(&format!("{value:?}"));
+                self.pending.code_hash = location.map(|(hash, _)| hash);
+                self.pending.offset = location.map(|(_, offset)| offset);
+            }
+            "stack" if self.with_stack => {
+                self.pending.stack = Some(format!("{value:?}"));
+            }
+            _ => {}
+        }
+    }
+
+    fn record_gas_remaining(&mut self, gas_remaining: i64) {
+        // Only the last recorded step can be completed.
+        if self.dropped_steps == 0
+            && let Some(last) = self.steps.last_mut()
+            && let Some(prev) = self.last_gas_remaining
         {
-            s.clear();
-            return s;
+            last.gas_consumed = Some(prev - gas_remaining);
+        }
+        self.pending.gas_remaining = Some(gas_remaining);
+    }
+}
+
+/// Extracts the code cell hash and the bit offset from the VM exec location.
+///
+/// The VM emits the location only as formatted args, so this is the only field
+/// which is not recorded as a typed value.
+fn parse_exec_location(location: &str) -> Option<(HashBytes, u16)> {
+    let mut parts = location
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty());
+
+    let hash = parts.find_map(|s| s.parse::<HashBytes>().ok())?;
+    let offset = parts.find_map(|s| s.parse::<u16>().ok())?;
+    Some((hash, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(limits: LogLimits, rows: usize) -> (String, VmLogDropped) {
+        let mut inner = Inner::new(256);
+        inner.set_limits(limits);
+        for i in 0..rows {
+            inner.push(format!("row {i}"));
         }
 
-        String::new()
+        let rows = VmLogRows {
+            inner: Arc::new(Mutex::new(inner)),
+        };
+        let dropped = rows.dropped();
+        (rows.to_string(), dropped)
+    }
+
+    #[test]
+    fn log_retention() {
+        // Each row is 6 bytes including newline.
+        let limits = |retention| LogLimits {
+            max_rows: None,
+            max_bytes: Some(24),
+            retention,
+        };
+
+        let (log, dropped) = collect(limits(LogRetention::Head), 10);
+        assert_eq!(log, "row 0\nrow 1\nrow 2\nrow 3\n");
+        assert_eq!(
+            dropped,
+            VmLogDropped {
+                rows: 6,
+                bytes: 36,
+                trace_steps: 0
+            }
+        );
+
+        let (log, dropped) = collect(limits(LogRetention::Tail), 10);
+        assert_eq!(log, "row 6\nrow 7\nrow 8\nrow 9\n");
+        assert_eq!(
+            dropped,
+            VmLogDropped {
+                rows: 6,
+                bytes: 36,
+                trace_steps: 0
+            }
+        );
+
+        let (log, dropped) = collect(limits(LogRetention::HeadTail), 10);
+        assert_eq!(
+            log,
+            "row 0\nrow 1\n... 6 rows (36 bytes) elided ...\nrow 8\nrow 9\n"
+        );
+        assert_eq!(
+            dropped,
+            VmLogDropped {
+                rows: 6,
+                bytes: 36,
+                trace_steps: 0
+            }
+        );
+
+        let (log, dropped) = collect(LogLimits::default(), 10);
+        assert_eq!(log.lines().count(), 10);
+        assert_eq!(dropped, VmLogDropped::default());
+    }
+
+    #[test]
+    fn display_resets_log() {
+        let mut inner = Inner::new(256);
+        inner.set_limits(LogLimits {
+            max_rows: Some(2),
+            max_bytes: None,
+            retention: LogRetention::Head,
+        });
+        let rows = VmLogRows {
+            inner: Arc::new(Mutex::new(inner)),
+        };
+
+        let push = |from: usize, to: usize| {
+            let mut inner = rows.inner.lock().unwrap();
+            for i in from..to {
+                inner.push(format!("row {i}"));
+            }
+        };
+
+        push(0, 5);
+        assert_eq!(rows.dropped().rows, 3);
+        assert_eq!(rows.to_string(), "row 0\nrow 1\n");
+
+        push(5, 6);
+        assert_eq!(rows.dropped(), VmLogDropped::default());
+        assert_eq!(rows.to_string(), "row 5\n");
+    }
+
+    #[test]
+    fn trace_steps_limit() {
+        let subscriber =
+            VmLogSubscriber::new(VmLogMask::empty(), 256).with_trace(TraceMode::Enabled);
+        if let Some(trace) = &mut subscriber.state.inner.lock().unwrap().trace {
+            trace.max_steps = 2;
+        }
+        let vm_log = subscriber.state().clone();
+
+        tracing::subscriber::with_default(subscriber, || {
+            for (gas, opcode) in [(100i64, "PUSHINT 1"), (82, "PUSHINT 2"), (64, "ADD")] {
+                tracing::trace!(target: VM_TARGET, gas_remaining = gas);
+                tracing::trace!(target: VM_TARGET, opcode = %opcode);
+            }
+            tracing::trace!(target: VM_TARGET, gas_remaining = 46i64);
+        });
+
+        let steps = vm_log.take_trace().unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].opcode, "PUSHINT 1");
+        assert_eq!(steps[0].gas_remaining, Some(100));
+        assert_eq!(steps[0].gas_consumed, Some(18));
+        assert_eq!(steps[1].gas_consumed, Some(18));
+        assert_eq!(vm_log.dropped().trace_steps, 1);
+    }
+
+    #[test]
+    fn sink_can_access_log() {
+        let vm_log = Arc::new(Mutex::new(None::<VmLogRows>));
+        let rows = Arc::new(Mutex::new(Vec::new()));
+
+        let subscriber = VmLogSubscriber::new(VmLogMask::MESSAGE, 256).with_sink(Some(Arc::new({
+            let vm_log = vm_log.clone();
+            let rows = rows.clone();
+            move |row: &str| {
+                // Must not deadlock.
+                let buffered = vm_log.lock().unwrap().as_ref().unwrap().to_string();
+                assert!(buffered.is_empty());
+                rows.lock().unwrap().push(row.to_owned());
+            }
+        })));
+        *vm_log.lock().unwrap() = Some(subscriber.state().clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::trace!(target: VM_TARGET, opcode = %"NOP");
+            tracing::trace!(target: VM_TARGET, message = %"exit");
+        });
+
+        assert_eq!(*rows.lock().unwrap(), ["execute NOP", "exit"]);
     }
 }
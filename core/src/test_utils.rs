@@ -0,0 +1,77 @@
+//! Fixtures shared by the emulator tests.
+
+use tycho_types::models::{
+    AccountState, BlockchainConfigParams, CurrencyCollection, IntMsgInfo, MsgInfo, OwnedMessage,
+    ShardAccount, StateInit, StdAddr,
+};
+use tycho_types::num::Tokens;
+use tycho_types::prelude::*;
+
+use crate::tx_emulator::{EmulationParams, TxEmulator};
+
+pub const NOW: u32 = 1_700_000_000;
+
+// DROP (selector), LDREF, DROP, PUSHINT 1, SENDRAWMSG
+pub const SEND_ONE: &[u8] = &[0x30, 0xd4, 0x30, 0x71, 0xfb, 0x00];
+// THROW 37
+pub const THROW: &[u8] = &[0xf2, 0x25];
+
+pub fn addr(byte: u8) -> StdAddr {
+    StdAddr::new(0, HashBytes([byte; 32]))
+}
+
+/// Root cell of the bundled tycho config.
+pub fn config_root() -> Cell {
+    Boc::decode(include_bytes!("../res/tycho_config.boc")).unwrap()
+}
+
+pub fn make_emulator() -> TxEmulator {
+    TxEmulator::new(BlockchainConfigParams::from_raw(config_root()), 0).unwrap()
+}
+
+pub fn make_params() -> EmulationParams {
+    EmulationParams {
+        block_unixtime: NOW,
+        block_lt: 1000,
+        ..Default::default()
+    }
+}
+
+/// Active account with the specified code and 10 TON balance.
+pub fn make_account(address: &StdAddr, code: &[u8]) -> ShardAccount {
+    let mut b = CellBuilder::new();
+    b.store_raw(code, code.len() as u16 * 8).unwrap();
+    let state = AccountState::Active(StateInit {
+        code: Some(b.build().unwrap()),
+        ..Default::default()
+    });
+    let balance = CurrencyCollection::new(10_000_000_000);
+    crate::util::make_shard_account(address.clone(), balance, state, NOW, 0).unwrap()
+}
+
+/// Internal message with 0.1 TON and the specified cells as body references.
+pub fn int_msg(src: &StdAddr, dst: &StdAddr, bounce: bool, refs: &[Cell]) -> Cell {
+    let mut body = CellBuilder::new();
+    for cell in refs {
+        body.store_reference(cell.clone()).unwrap();
+    }
+
+    CellBuilder::build_from(OwnedMessage {
+        info: MsgInfo::Int(IntMsgInfo {
+            ihr_disabled: true,
+            bounce,
+            bounced: false,
+            src: src.clone().into(),
+            dst: dst.clone().into(),
+            value: CurrencyCollection::new(100_000_000),
+            extra_flags: Default::default(),
+            fwd_fee: Tokens::ZERO,
+            created_lt: 0,
+            created_at: 0,
+        }),
+        init: None,
+        body: body.build().unwrap().into(),
+        layout: None,
+    })
+    .unwrap()
+}
@@ -1,26 +1,33 @@
 use anyhow::{Context, Result};
 use num_bigint::BigInt;
 use tycho_types::models::{
-    BlockchainConfigParams, CurrencyCollection, ExtInMsgInfo, ExtraCurrencyCollection,
-    GlobalCapability, IntMsgInfo, MsgInfo, OwnedMessage, SimpleLib, StdAddr,
+    AccountState, BlockchainConfigParams, ConfigParam31, CurrencyCollection, ExtInMsgInfo,
+    ExtraCurrencyCollection, GasLimitsPrices, GlobalCapability, IntAddr, IntMsgInfo, Message,
+    MsgInfo, OwnedMessage, ShardAccount, SimpleLib, StdAddr,
 };
 use tycho_types::num::Tokens;
 use tycho_types::prelude::*;
 use tycho_vm::{
     BehaviourModifiers, CustomSmcInfo, GasParams, SafeRc, SmcInfo, SmcInfoBase, SmcInfoTonV6,
-    Stack, Tuple, VmState, VmVersion, tuple,
+    Stack, Tuple, UnpackedInMsgSmcInfo, VmState, VmVersion, tuple,
 };
 
-use crate::subscriber::VmLogSubscriber;
-use crate::util::make_vm_log_mask;
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::subscriber::{LogLimits, LogSink, TraceMode, VmLogRows, VmLogSubscriber};
+use crate::util::{make_vm_log_mask, vm_log_capacity};
 
 const MAX_GAS: u64 = 1_000_000;
 const BASE_GAS_PRICE: u64 = 1000 << 16;
 
+#[derive(Clone)]
 pub struct TvmEmulator {
     pub code: Cell,
     pub data: Cell,
     pub args: Args,
+    /// Streams VM log rows instead of buffering them.
+    ///
+    /// Not captured by snapshots, so restoring never brings back a stale callback.
+    pub log_sink: Option<LogSink>,
 }
 
 impl TvmEmulator {
@@ -32,58 +39,126 @@ impl TvmEmulator {
                 verbosity,
                 ..Default::default()
             },
+            log_sink: None,
         }
     }
 
     pub fn make_logger(&self) -> VmLogSubscriber {
-        let mut log_max_size = 256;
-        if self.args.verbosity > 4 {
-            log_max_size = 32 << 20;
-        } else if self.args.verbosity > 0 {
-            log_max_size = 1 << 20;
-        }
-
         let mask = make_vm_log_mask(self.args.verbosity, false);
-        VmLogSubscriber::new(mask, log_max_size)
+        VmLogSubscriber::new(mask, vm_log_capacity(mask))
+            .with_limits(self.args.log_limits)
+            .with_trace(self.args.trace)
+            .with_sink(self.log_sink.clone())
     }
 
-    pub fn send_external_message(&mut self, body: Cell) -> Answer {
-        let stack = self.args.build_stack(0, body, -1);
+    pub fn send_external_message(&mut self, body: Cell) -> Result<Answer> {
+        let message = self.args.build_external_message(body.clone());
+        let stack = self.args.build_stack(0, message, body);
         self.run_method(-1, stack)
     }
 
-    pub fn send_internal_message(&mut self, body: Cell, amount: u64) -> Answer {
-        let stack = self.args.build_stack(amount, body, 0);
-        self.run_method(0, stack)
-    }
+    pub fn send_internal_message(&mut self, body: Cell, amount: u64) -> Result<Answer> {
+        let info = self.args.default_int_msg_info(amount);
+        let message = CellBuilder::build_from(OwnedMessage {
+            info: MsgInfo::Int(info.clone()),
+            init: None,
+            body: body.clone().into(),
+            layout: None,
+        })
+        .unwrap();
 
-    fn run_method(&mut self, method_id: i32, stack: Stack) -> Answer {
-        let prev_gas_params = self.args.gas_params;
-        if self.args.gas_params.is_none() {
-            let is_internal = method_id == 0;
-            let (limit, credit) = if is_internal {
-                (self.args.amount.saturating_mul(1000), 0)
-            } else {
-                (0, 10000)
-            };
+        self.run_internal(message, info, body, None)
+    }
 
-            self.args.gas_params = Some(GasParams {
-                max: MAX_GAS,
-                limit,
-                credit,
-                price: BASE_GAS_PRICE,
-            });
+    /// Runs `recv_internal` with a complete internal message.
+    ///
+    /// Stack and `in_msg` params of c7 are built from the message,
+    /// its destination must be the emulated account.
+    pub fn send_internal_message_full(&mut self, message: Cell) -> Result<Answer> {
+        let parsed = message
+            .parse::<Message<'_>>()
+            .context("Failed to unpack message")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let MsgInfo::Int(info) = parsed.info else {
+            return Err(anyhow::anyhow!("Expected an internal message"))
+                .with_kind(ErrorKind::InvalidInput);
+        };
+        if info.dst != IntAddr::Std(self.args.address()) {
+            return Err(anyhow::anyhow!(
+                "Message destination doesn't match the emulator address"
+            ))
+            .with_kind(ErrorKind::InvalidInput);
         }
 
-        let res = self.run_get_method(method_id, stack);
-        self.args.gas_params = prev_gas_params;
+        let body = CellBuilder::build_from(parsed.body).context("Failed to copy message body")?;
+        let state_init = match &parsed.init {
+            Some(init) => Some(CellBuilder::build_from(init).context("Failed to copy state init")?),
+            None => None,
+        };
+
+        self.run_internal(message, info, body, state_init)
+    }
+
+    fn run_internal(
+        &mut self,
+        message: Cell,
+        info: IntMsgInfo,
+        body: Cell,
+        state_init: Option<Cell>,
+    ) -> Result<Answer> {
+        let amount = u64::try_from(info.value.tokens.into_inner()).unwrap_or(u64::MAX);
+        let stack = self.args.build_stack(amount, message, body);
+
+        let src_addr = CellBuilder::build_from(&info.src)
+            .map(CellSliceParts::from)
+            .unwrap();
+        let in_msg = UnpackedInMsgSmcInfo {
+            bounce: info.bounce,
+            bounced: info.bounced,
+            src_addr,
+            fwd_fee: info.fwd_fee,
+            created_lt: info.created_lt,
+            created_at: info.created_at,
+            original_value: info.value.tokens,
+            remaining_value: info.value.clone(),
+            state_init,
+        };
+
+        let prev_amount = std::mem::replace(&mut self.args.amount, amount);
+        let prev_in_msg = self.args.in_msg.replace(InMsgArgs {
+            value: info.value,
+            unpacked: in_msg.into_tuple(),
+        });
+
+        let res = self.run_method(0, stack);
+
+        self.args.amount = prev_amount;
+        self.args.in_msg = prev_in_msg;
+        res
+    }
+
+    fn run_method(&mut self, method_id: i32, stack: Stack) -> Result<Answer> {
+        let gas_params = match self.args.explicit_gas_params()? {
+            Some(gas_params) => gas_params,
+            None => self.args.compute_gas_params(method_id == 0)?,
+        };
+
+        let res = self.run_vm(method_id, stack, gas_params);
         self.code = res.code.clone();
         self.data = res.data.clone();
 
-        res
+        Ok(res)
+    }
+
+    pub fn run_get_method(&self, method_id: i32, stack: Stack) -> Result<Answer> {
+        let gas_params = self
+            .args
+            .explicit_gas_params()?
+            .unwrap_or_else(GasParams::getter);
+        Ok(self.run_vm(method_id, stack, gas_params))
     }
 
-    pub fn run_get_method(&self, method_id: i32, mut stack: Stack) -> Answer {
+    fn run_vm(&self, method_id: i32, mut stack: Stack, gas_params: GasParams) -> Answer {
         // Prepare stack
         stack
             .items
@@ -97,19 +172,26 @@ impl TvmEmulator {
             .map(|c| (c.enable_signature_domains, c.signature_with_id))
             .unwrap_or_default();
 
+        let smc_info = self.args.build_smc_info(self.code.clone());
+        let c7 = self.args.return_c7.then(|| smc_info.build_c7());
+        let vm_version = match smc_info.version() {
+            VmVersion::Ton(version) | VmVersion::Everscale(version) => version,
+        };
+
         let mut b = VmState::builder()
             .with_raw_stack(SafeRc::new(stack))
             .with_code(self.code.clone())
             .with_data(self.data.clone())
-            .with_smc_info(self.args.build_smc_info(self.code.clone()))
+            .with_smc_info(smc_info)
             .with_libraries(&self.args.libraries)
-            .with_gas(self.args.gas_params.unwrap_or_else(GasParams::getter))
+            .with_gas(gas_params)
             .with_init_selector(false)
             .with_modifiers(BehaviourModifiers {
                 enable_signature_domains,
                 signature_with_id,
                 chksig_always_succeed: self.args.ignore_chksig,
-                log_mask: make_vm_log_mask(self.args.verbosity, false),
+                log_mask: make_vm_log_mask(self.args.verbosity, false)
+                    | self.args.trace.vm_log_mask(),
                 ..Default::default()
             });
 
@@ -151,9 +233,47 @@ impl TvmEmulator {
             gas_used,
             debug_log,
             missing_library,
+            c7,
+            vm_version,
         }
     }
 
+    /// Runs a get-method with a separate VM logger.
+    pub fn run_get_method_logged(&self, method_id: i32, stack: Stack) -> Result<GetMethodOutcome> {
+        let subscriber = self.make_logger();
+        let vm_log = subscriber.state().clone();
+        let _tracing = tracing::subscriber::set_default(subscriber);
+
+        Ok(GetMethodOutcome {
+            method_id,
+            answer: self.run_get_method(method_id, stack)?,
+            vm_log,
+        })
+    }
+
+    /// Calls `f` with the overrides applied to the emulator params.
+    ///
+    /// Previous gas limit and log limits are restored afterwards, even on error.
+    pub fn with_overrides<T, F>(&mut self, overrides: CallOverrides, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let prev_gas_limit = self.args.gas_limit;
+        let prev_log_limits = self.args.log_limits;
+        if let Some(gas_limit) = overrides.gas_limit.filter(|&gas_limit| gas_limit > 0) {
+            self.set_gas_limit(gas_limit);
+        }
+        if let Some(log_limits) = overrides.log_limits {
+            self.args.log_limits = log_limits;
+        }
+
+        let res = f(self);
+
+        self.args.gas_limit = prev_gas_limit;
+        self.args.log_limits = prev_log_limits;
+        res
+    }
+
     pub fn set_c7(
         &mut self,
         address: StdAddr,
@@ -171,16 +291,103 @@ impl TvmEmulator {
         }
     }
 
+    /// Replaces code, data and account-related c7 params with the ones from the account.
+    ///
+    /// Only active accounts are allowed.
+    pub fn set_account(&mut self, account: &ShardAccount) -> Result<()> {
+        self.set_account_impl(account)
+            .with_kind(ErrorKind::InvalidInput)
+    }
+
+    fn set_account_impl(&mut self, account: &ShardAccount) -> Result<()> {
+        let account = account
+            .load_account()
+            .context("Failed to unpack account")?
+            .context("Account does not exist")?;
+
+        let state_init = match account.state {
+            AccountState::Active(state_init) => state_init,
+            AccountState::Uninit => anyhow::bail!("Account is not initialized"),
+            AccountState::Frozen(_) => anyhow::bail!("Account is frozen"),
+        };
+
+        let IntAddr::Std(address) = account.address else {
+            anyhow::bail!("var_addr is not supported");
+        };
+
+        let code = state_init.code.context("Account has no code")?;
+        let balance = u64::try_from(account.balance.tokens.into_inner())
+            .context("Account balance is too big")?;
+
+        self.code = code;
+        self.data = state_init.data.unwrap_or_else(Cell::empty_cell);
+        self.args.address = Some(address);
+        self.args.balance = balance;
+        self.args.extra = account.balance.other;
+        self.args.due_payment = account.storage_stat.due_payment.unwrap_or_default();
+        Ok(())
+    }
+
+    /// Returns a copy of the emulator with the state of the specified account.
+    ///
+    /// The emulator itself is left untouched.
+    pub fn for_account(&self, account: &ShardAccount) -> Result<Self> {
+        let mut emulator = self.clone();
+        emulator.set_account(account)?;
+        Ok(emulator)
+    }
+
+    /// Captures code, data and all args.
+    ///
+    /// The log sink is not captured.
+    ///
+    /// Cells and tuples are shared, so taking a snapshot is cheap.
+    pub fn snapshot(&self) -> TvmEmulatorSnapshot {
+        TvmEmulatorSnapshot {
+            code: self.code.clone(),
+            data: self.data.clone(),
+            args: self.args.clone(),
+        }
+    }
+
+    /// Restores the captured state. The same snapshot can be restored any number of times.
+    pub fn restore(&mut self, snapshot: &TvmEmulatorSnapshot) {
+        self.code = snapshot.code.clone();
+        self.data = snapshot.data.clone();
+        self.args = snapshot.args.clone();
+    }
+
+    /// Sets an explicit gas limit for the following runs.
+    ///
+    /// Max gas and gas price are resolved on each run.
     pub fn set_gas_limit(&mut self, gas_limit: u64) {
-        self.args.gas_params = Some(GasParams {
-            max: MAX_GAS,
-            limit: gas_limit,
-            credit: 0,
-            price: BASE_GAS_PRICE,
-        });
+        self.args.gas_limit = Some(gas_limit);
     }
 }
 
+/// Temporary overrides of the emulator params for a single call.
+#[derive(Default, Clone, Copy)]
+pub struct CallOverrides {
+    /// Zero means no override.
+    pub gas_limit: Option<u64>,
+    pub log_limits: Option<LogLimits>,
+}
+
+/// Get-method answer with its VM log.
+pub struct GetMethodOutcome {
+    pub method_id: i32,
+    pub answer: Answer,
+    pub vm_log: VmLogRows,
+}
+
+/// Saved state of the [`TvmEmulator`].
+#[derive(Clone)]
+pub struct TvmEmulatorSnapshot {
+    code: Cell,
+    data: Cell,
+    args: Args,
+}
+
 pub struct Answer {
     pub code: Cell,
     pub data: Cell,
@@ -191,11 +398,16 @@ pub struct Answer {
     pub gas_used: u64,
     pub debug_log: String,
     pub missing_library: Option<HashBytes>,
+    /// c7 used for the run (if requested).
+    pub c7: Option<SafeRc<Tuple>>,
+    /// Global version used for the c7 layout and the instruction set.
+    pub vm_version: u32,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Args {
-    pub gas_params: Option<GasParams>,
+    /// Explicit gas limit (see [`TvmEmulator::set_gas_limit`]).
+    pub gas_limit: Option<u64>,
     pub raw_c7: Option<SafeRc<Tuple>>,
     pub now: Option<u32>,
     pub rand_seed: Option<HashBytes>,
@@ -203,17 +415,46 @@ pub struct Args {
     pub amount: u64,
     pub balance: u64,
     pub extra: ExtraCurrencyCollection,
+    pub due_payment: Tokens,
     pub verbosity: i32,
     pub debug_enabled: bool,
+    /// Whether to return the built c7 in the answer.
+    pub return_c7: bool,
+    /// Overrides the global version from config.
+    pub global_version: Option<u32>,
+    /// Structured trace mode.
+    pub trace: TraceMode,
+    /// Overrides verbosity-based VM log limits.
+    pub log_limits: LogLimits,
 
     pub address: Option<StdAddr>,
     pub config: Option<ParsedConfig>,
     pub libraries: Option<Dict<HashBytes, SimpleLib>>,
     pub prev_blocks_info: Option<SafeRc<Tuple>>,
+    /// Inbound message of the current `recv_internal` call.
+    pub in_msg: Option<InMsgArgs>,
+}
+
+#[derive(Clone)]
+pub struct InMsgArgs {
+    pub value: CurrencyCollection,
+    pub unpacked: SafeRc<Tuple>,
 }
 
 impl Args {
     fn build_smc_info(&self, code: Cell) -> Box<dyn SmcInfo> {
+        let smc_info = self.build_smc_info_impl(code);
+        match self.global_version {
+            // Use the same version for the instruction set.
+            Some(version) => Box::new(CustomSmcInfo {
+                version: VmVersion::Ton(version),
+                c7: smc_info.build_c7(),
+            }),
+            None => smc_info,
+        }
+    }
+
+    fn build_smc_info_impl(&self, code: Cell) -> Box<dyn SmcInfo> {
         if let Some(c7) = self.raw_c7.clone() {
             return Box::new(CustomSmcInfo {
                 version: VmVersion::LATEST_TON,
@@ -236,12 +477,12 @@ impl Args {
             .with_account_balance(balance)
             .with_account_addr(self.address().into());
 
-        let mut global_version = 1;
+        let mut global_version = self.global_version.unwrap_or(1);
         let mut unpacked_config = None;
         if let Some(config) = &self.config {
             b = b.with_config(config.params.clone());
 
-            global_version = config.version;
+            global_version = self.global_version.unwrap_or(config.version);
 
             if global_version >= 6 {
                 unpacked_config = Some(
@@ -258,7 +499,12 @@ impl Args {
         let mut b = b
             .require_ton_v4()
             .with_code(code)
-            .with_message_balance(CurrencyCollection::ZERO)
+            .with_message_balance(
+                self.in_msg
+                    .as_ref()
+                    .map(|msg| msg.value.clone())
+                    .unwrap_or(CurrencyCollection::ZERO),
+            )
             .with_storage_fees(Tokens::ZERO);
         if let Some(prev_blocks_info) = &self.prev_blocks_info {
             b = b.with_prev_blocks_info(prev_blocks_info.clone());
@@ -268,7 +514,7 @@ impl Args {
             return Box::new(b);
         }
 
-        let mut b = b.require_ton_v6().with_due_payment(Tokens::ZERO);
+        let mut b = b.require_ton_v6().with_due_payment(self.due_payment);
         if let Some(unpacked_config) = unpacked_config {
             b = b.with_unpacked_config(unpacked_config);
         }
@@ -277,7 +523,9 @@ impl Args {
             return Box::new(b);
         }
 
-        let b = b.require_ton_v11().with_unpacked_in_msg(None);
+        let b = b
+            .require_ton_v11()
+            .with_unpacked_in_msg(self.in_msg.as_ref().map(|msg| msg.unpacked.clone()));
 
         // Overwrite VM version if the latest smc info is used.
         Box::new(CustomSmcInfo {
@@ -286,44 +534,141 @@ impl Args {
         })
     }
 
-    fn build_stack(&self, message_amount: u64, message_body: Cell, selector: i32) -> Stack {
+    /// Returns gas prices for the account workchain and whether the account is special.
+    fn gas_prices(&self) -> Result<Option<(GasLimitsPrices, bool)>> {
+        let Some(config) = &self.config else {
+            return Ok(None);
+        };
+
+        let address = self.address();
+        let prices = config
+            .params
+            .get_gas_prices(address.is_masterchain())
+            .context("Failed to get gas prices")
+            .with_kind(ErrorKind::ConfigError)?;
+
+        let is_special = if address.is_masterchain() {
+            let addresses = config
+                .params
+                .get::<ConfigParam31>()
+                .context("Failed to get fundamental addresses")
+                .with_kind(ErrorKind::ConfigError)?;
+            match addresses {
+                Some(addresses) => addresses
+                    .contains_key(address.address)
+                    .context("Invalid fundamental addresses")
+                    .with_kind(ErrorKind::ConfigError)?,
+                None => false,
+            }
+        } else {
+            false
+        };
+
+        Ok(Some((prices, is_special)))
+    }
+
+    /// Returns gas params for the explicit gas limit (if set).
+    ///
+    /// Max gas and gas price are taken from the current config and address,
+    /// falling back to the hardcoded prices when no config is set.
+    fn explicit_gas_params(&self) -> Result<Option<GasParams>> {
+        let Some(limit) = self.gas_limit else {
+            return Ok(None);
+        };
+
+        let (max, price) = match self.gas_prices()? {
+            Some((prices, is_special)) => {
+                let max = if is_special {
+                    prices.special_gas_limit
+                } else {
+                    prices.gas_limit
+                };
+                (max, prices.gas_price)
+            }
+            None => (MAX_GAS, BASE_GAS_PRICE),
+        };
+
+        Ok(Some(GasParams {
+            max,
+            limit,
+            credit: 0,
+            price,
+        }))
+    }
+
+    /// Computes gas params in the same way as the compute phase does.
+    ///
+    /// Falls back to the hardcoded prices when no config is set.
+    fn compute_gas_params(&self, is_internal: bool) -> Result<GasParams> {
+        let message_amount = if is_internal { self.amount } else { 0 };
+
+        let Some((prices, is_special)) = self.gas_prices()? else {
+            let (limit, credit) = if is_internal {
+                (message_amount.saturating_mul(1000), 0)
+            } else {
+                (0, 10000)
+            };
+
+            return Ok(GasParams {
+                max: MAX_GAS,
+                limit,
+                credit,
+                price: BASE_GAS_PRICE,
+            });
+        };
+
+        let max = if is_special {
+            prices.special_gas_limit
+        } else {
+            gas_bought_for(&prices, self.balance)
+        };
+        let limit = gas_bought_for(&prices, message_amount).min(max);
+        let credit = if is_internal {
+            0
+        } else {
+            prices.gas_credit.min(max)
+        };
+
+        Ok(GasParams {
+            max,
+            limit,
+            credit,
+            price: prices.gas_price,
+        })
+    }
+
+    fn effective_balance(&self) -> u64 {
+        if self.balance > 0 {
+            self.balance
+        } else {
+            10_000_000_000
+        }
+    }
+
+    fn build_stack(&self, message_amount: u64, message: Cell, message_body: Cell) -> Stack {
         Stack {
             items: tuple![
-                int if self.balance > 0 {
-                    self.balance
-                } else {
-                    10_000_000_000
-                },
+                int self.effective_balance(),
                 int message_amount,
-                cell if selector == 0 {
-                    self.build_internal_message(message_amount, message_body.clone())
-                } else {
-                    self.build_external_message(message_body.clone())
-                },
+                cell message,
                 slice CellSliceParts::from(message_body),
             ],
         }
     }
 
-    fn build_internal_message(&self, amount: u64, body: Cell) -> Cell {
-        CellBuilder::build_from(OwnedMessage {
-            info: MsgInfo::Int(IntMsgInfo {
-                ihr_disabled: true,
-                bounce: true,
-                bounced: false,
-                src: StdAddr::new(-1, HashBytes::ZERO).into(),
-                dst: self.address().into(),
-                value: CurrencyCollection::new(amount as _),
-                extra_flags: Default::default(),
-                fwd_fee: Tokens::ZERO,
-                created_lt: 0,
-                created_at: 0,
-            }),
-            init: None,
-            body: body.into(),
-            layout: None,
-        })
-        .unwrap()
+    fn default_int_msg_info(&self, amount: u64) -> IntMsgInfo {
+        IntMsgInfo {
+            ihr_disabled: true,
+            bounce: true,
+            bounced: false,
+            src: StdAddr::new(-1, HashBytes::ZERO).into(),
+            dst: self.address().into(),
+            value: CurrencyCollection::new(amount as _),
+            extra_flags: Default::default(),
+            fwd_fee: Tokens::ZERO,
+            created_lt: 0,
+            created_at: 0,
+        }
     }
 
     fn build_external_message(&self, body: Cell) -> Cell {
@@ -340,13 +685,24 @@ impl Args {
         .unwrap()
     }
 
-    fn address(&self) -> StdAddr {
+    pub fn address(&self) -> StdAddr {
         self.address
             .clone()
             .unwrap_or_else(|| StdAddr::new(0, HashBytes::ZERO))
     }
 }
 
+/// Amount of gas which can be bought for the specified amount of nanotokens.
+fn gas_bought_for(prices: &GasLimitsPrices, amount: u64) -> u64 {
+    if amount < prices.flat_gas_price {
+        return 0;
+    }
+
+    let amount = ((amount - prices.flat_gas_price) as u128) << 16;
+    let gas = amount / prices.gas_price.max(1) as u128 + prices.flat_gas_limit as u128;
+    gas.min(prices.gas_limit as u128) as u64
+}
+
 #[derive(Clone)]
 pub struct ParsedConfig {
     pub params: BlockchainConfigParams,
@@ -362,11 +718,13 @@ impl ParsedConfig {
 
         // Try to unpack config to return error early.
         tycho_vm::SmcInfoTonV6::unpack_config(&params, 0)
-            .context("Failed to unpack config params")?;
+            .context("Failed to unpack config params")
+            .with_kind(ErrorKind::ConfigError)?;
 
         let global = params
             .get_global_version()
-            .context("Failed to get global version")?;
+            .context("Failed to get global version")
+            .with_kind(ErrorKind::ConfigError)?;
 
         let capabilities = global.capabilities;
         let enable_signature_domains = capabilities.contains(GlobalCapability::CapSignatureDomain);
@@ -377,6 +735,7 @@ impl ParsedConfig {
             params
                 .get_global_id()
                 .context("Global id is mandatory (param 19)")
+                .with_kind(ErrorKind::ConfigError)
                 .map(Some)?
         } else {
             None
@@ -393,6 +752,10 @@ impl ParsedConfig {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tycho_types::models::StateInit;
+
     use super::*;
 
     #[test]
@@ -405,6 +768,367 @@ mod tests {
         }
     }
 
+    #[test]
+    fn gas_params_from_config() {
+        let root = Boc::decode(include_bytes!("../res/ton_config.boc")).unwrap();
+        let config = ParsedConfig::try_from_root(root).unwrap();
+        let prices = config.params.get_gas_prices(false).unwrap();
+
+        let mut args = Args {
+            config: Some(config),
+            balance: 1_000_000_000_000,
+            amount: prices.flat_gas_price,
+            ..Default::default()
+        };
+
+        let internal = args.compute_gas_params(true).unwrap();
+        assert_eq!(internal.limit, prices.flat_gas_limit);
+        assert_eq!(internal.max, prices.gas_limit);
+        assert_eq!(internal.credit, 0);
+        assert_eq!(internal.price, prices.gas_price);
+
+        args.amount = 0;
+        let external = args.compute_gas_params(false).unwrap();
+        assert_eq!(external.limit, 0);
+        assert_eq!(external.credit, prices.gas_credit);
+
+        // Empty balance buys no gas, as in the compute phase.
+        args.balance = 0;
+        let empty = args.compute_gas_params(false).unwrap();
+        assert_eq!(empty.max, 0);
+        assert_eq!(empty.credit, 0);
+    }
+
+    #[test]
+    fn gas_limit_uses_config_prices() {
+        let root = Boc::decode(include_bytes!("../res/ton_config.boc")).unwrap();
+        let config = ParsedConfig::try_from_root(root).unwrap();
+        let prices = config.params.get_gas_prices(false).unwrap();
+
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        emulator.set_gas_limit(1000);
+        let gas_params = emulator.args.explicit_gas_params().unwrap().unwrap();
+        assert_eq!(gas_params.max, MAX_GAS);
+        assert_eq!(gas_params.price, BASE_GAS_PRICE);
+
+        // Config set after the gas limit is still used.
+        emulator.args.config = Some(config);
+        let gas_params = emulator.args.explicit_gas_params().unwrap().unwrap();
+        assert_eq!(gas_params.limit, 1000);
+        assert_eq!(gas_params.max, prices.gas_limit);
+        assert_eq!(gas_params.credit, 0);
+        assert_eq!(gas_params.price, prices.gas_price);
+    }
+
+    #[test]
+    fn invalid_gas_prices() {
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        emulator.args.config = Some(ParsedConfig {
+            params: BlockchainConfigParams::from_raw(Cell::empty_cell()),
+            version: 0,
+            enable_signature_domains: false,
+            signature_with_id: None,
+        });
+
+        emulator.set_gas_limit(1000);
+        let e = emulator.run_get_method(0, Stack::default()).unwrap_err();
+        assert_eq!(ErrorKind::of(&e), ErrorKind::ConfigError);
+
+        let e = emulator
+            .send_external_message(Cell::empty_cell())
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&e), ErrorKind::ConfigError);
+    }
+
+    #[test]
+    fn set_active_account_only() {
+        let address = StdAddr::new(0, HashBytes([0x11; 32]));
+        let balance = CurrencyCollection::new(1_000_000_000);
+        let code = CellBuilder::build_from(0xdeadbeefu32).unwrap();
+
+        let make_account = |state| {
+            crate::util::make_shard_account(address.clone(), balance.clone(), state, 0, 0).unwrap()
+        };
+
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+
+        let uninit = make_account(AccountState::Uninit);
+        assert!(emulator.set_account(&uninit).is_err());
+
+        let active = make_account(AccountState::Active(StateInit {
+            code: Some(code.clone()),
+            ..Default::default()
+        }));
+        emulator.set_account(&active).unwrap();
+        assert_eq!(emulator.code, code);
+        assert_eq!(emulator.args.address, Some(address));
+        assert_eq!(emulator.args.balance, 1_000_000_000);
+    }
+
+    #[test]
+    fn for_account_keeps_state() {
+        let address = StdAddr::new(0, HashBytes([0x11; 32]));
+        let code = CellBuilder::build_from(0xdeadbeefu32).unwrap();
+        let account = crate::util::make_shard_account(
+            address.clone(),
+            CurrencyCollection::new(1_000_000_000),
+            AccountState::Active(StateInit {
+                code: Some(code.clone()),
+                ..Default::default()
+            }),
+            0,
+            0,
+        )
+        .unwrap();
+
+        let emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        let temp = emulator.for_account(&account).unwrap();
+        assert_eq!(temp.code, code);
+        assert_eq!(temp.args.address, Some(address));
+        assert_eq!(temp.args.balance, 1_000_000_000);
+
+        assert_eq!(emulator.code, Cell::empty_cell());
+        assert!(emulator.args.address.is_none());
+        assert_eq!(emulator.args.balance, 0);
+    }
+
+    #[test]
+    fn send_internal_message() {
+        let address = StdAddr::new(0, HashBytes([0x11; 32]));
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        emulator.args.address = Some(address.clone());
+        emulator.args.balance = 1_000_000_000;
+
+        let res = emulator
+            .send_internal_message(Cell::empty_cell(), 500)
+            .unwrap();
+        assert_eq!(res.exit_code, 0);
+
+        // balance, msg_value, in_msg, in_msg_body, selector
+        let items = &res.stack.items;
+        assert_eq!(*items[1].as_int().unwrap(), BigInt::from(500));
+
+        let msg = items[2].as_cell().unwrap().parse::<Message<'_>>().unwrap();
+        let MsgInfo::Int(info) = msg.info else {
+            panic!("internal message expected");
+        };
+        assert_eq!(info.src, StdAddr::new(-1, HashBytes::ZERO).into());
+        assert_eq!(info.dst, address.into());
+        assert_eq!(info.value, CurrencyCollection::new(500));
+        assert!(info.bounce);
+
+        // Message params are not kept after the call.
+        assert_eq!(emulator.args.amount, 0);
+        assert!(emulator.args.in_msg.is_none());
+    }
+
+    #[test]
+    fn send_internal_message_full() {
+        let address = StdAddr::new(0, HashBytes([0x11; 32]));
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        emulator.args.address = Some(address.clone());
+
+        let make_message = |dst: &StdAddr| {
+            CellBuilder::build_from(OwnedMessage {
+                info: MsgInfo::Int(IntMsgInfo {
+                    ihr_disabled: true,
+                    bounce: false,
+                    bounced: false,
+                    src: StdAddr::new(0, HashBytes([0x22; 32])).into(),
+                    dst: dst.clone().into(),
+                    value: CurrencyCollection::new(700),
+                    extra_flags: Default::default(),
+                    fwd_fee: Tokens::ZERO,
+                    created_lt: 0,
+                    created_at: 0,
+                }),
+                init: None,
+                body: Cell::empty_cell().into(),
+                layout: None,
+            })
+            .unwrap()
+        };
+
+        let message = make_message(&address);
+        let res = emulator
+            .send_internal_message_full(message.clone())
+            .unwrap();
+        assert_eq!(res.exit_code, 0);
+
+        let items = &res.stack.items;
+        assert_eq!(*items[1].as_int().unwrap(), BigInt::from(700));
+        assert_eq!(items[2].as_cell().unwrap(), &message);
+
+        let other = StdAddr::new(0, HashBytes([0x33; 32]));
+        let e = emulator
+            .send_internal_message_full(make_message(&other))
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&e), ErrorKind::InvalidInput);
+
+        let e = emulator
+            .send_internal_message_full(Cell::empty_cell())
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&e), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn overrides_are_restored() {
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        let overrides = CallOverrides {
+            gas_limit: Some(1000),
+            log_limits: Some(LogLimits {
+                max_rows: Some(10),
+                ..Default::default()
+            }),
+        };
+
+        for fail in [false, true] {
+            let res = emulator.with_overrides(overrides, |emulator| {
+                assert_eq!(emulator.args.gas_limit, Some(1000));
+                assert_eq!(emulator.args.log_limits.max_rows, Some(10));
+                if fail {
+                    anyhow::bail!("call failed");
+                }
+                Ok(())
+            });
+            assert_eq!(res.is_err(), fail);
+
+            assert!(emulator.args.gas_limit.is_none());
+            assert!(emulator.args.log_limits.max_rows.is_none());
+        }
+
+        // Zero gas limit keeps the current gas limit.
+        emulator.set_gas_limit(500);
+        let overrides = CallOverrides {
+            gas_limit: Some(0),
+            log_limits: None,
+        };
+        emulator
+            .with_overrides(overrides, |emulator| {
+                assert_eq!(emulator.args.gas_limit, Some(500));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(emulator.args.gas_limit, Some(500));
+    }
+
+    #[test]
+    fn global_version_override() {
+        let mut emulator = TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), 0);
+        emulator.args.global_version = Some(9);
+
+        let res = emulator.run_get_method(0, Stack::default()).unwrap();
+        assert_eq!(res.vm_version, 9);
+    }
+
+    #[test]
+    fn structured_trace() {
+        let code = CellBuilder::build_from(0x71_u8).unwrap();
+        let mut emulator = TvmEmulator::new(code.clone(), Cell::empty_cell(), 0);
+        emulator.args.trace = TraceMode::WithStack;
+
+        let subscriber = emulator.make_logger();
+        let vm_log = subscriber.state().clone();
+        {
+            let _tracing = tracing::subscriber::set_default(subscriber);
+            emulator.run_get_method(0, Stack::default()).unwrap();
+        }
+
+        let steps = vm_log.take_trace().unwrap();
+        let first = steps.first().unwrap();
+        assert!(first.opcode.starts_with("PUSHINT"));
+        assert_eq!(first.code_hash, Some(*code.repr_hash()));
+        assert_eq!(first.offset, Some(0));
+        assert!(first.gas_consumed.unwrap() > 0);
+        assert!(steps.iter().all(|step| step.stack.is_some()));
+
+        // Text log is not affected by the trace mode.
+        assert!(vm_log.to_string().is_empty());
+    }
+
+    #[test]
+    fn log_sink_streams_rows() {
+        let code = CellBuilder::build_from(0x71_u8).unwrap();
+        let mut emulator = TvmEmulator::new(code, Cell::empty_cell(), 1);
+
+        let rows = Arc::new(Mutex::new(Vec::new()));
+        emulator.log_sink = Some(Arc::new({
+            let rows = rows.clone();
+            move |row: &str| rows.lock().unwrap().push(row.to_owned())
+        }));
+
+        let subscriber = emulator.make_logger();
+        let vm_log = subscriber.state().clone();
+        {
+            let _tracing = tracing::subscriber::set_default(subscriber);
+            emulator.run_get_method(0, Stack::default()).unwrap();
+        }
+
+        let rows = rows.lock().unwrap();
+        assert!(rows.iter().any(|row| row.starts_with("execute PUSHINT")));
+        assert!(vm_log.to_string().is_empty());
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let code = CellBuilder::build_from(0x7f_u8).unwrap();
+        let address = StdAddr::new(0, HashBytes([0x11; 32]));
+        let raw_c7: Tuple = tuple![int 1, int 2];
+
+        let mut emulator = TvmEmulator::new(code.clone(), Cell::empty_cell(), 0);
+        emulator.args.now = Some(100);
+        emulator.args.address = Some(address.clone());
+        emulator.args.raw_c7 = Some(SafeRc::new(raw_c7));
+        emulator.set_gas_limit(1000);
+
+        let snapshot = emulator.snapshot();
+
+        for _ in 0..2 {
+            emulator.code = Cell::empty_cell();
+            emulator.data = code.clone();
+            emulator.args.now = Some(200);
+            emulator.args.address = Some(StdAddr::new(-1, HashBytes([0x22; 32])));
+            emulator.args.raw_c7 = None;
+            emulator.set_gas_limit(2000);
+
+            emulator.restore(&snapshot);
+            assert_eq!(emulator.code, code);
+            assert_eq!(emulator.data, Cell::empty_cell());
+            assert_eq!(emulator.args.now, Some(100));
+            assert_eq!(emulator.args.address, Some(address.clone()));
+            assert_eq!(emulator.args.raw_c7.as_ref().map(|c7| c7.len()), Some(2));
+            assert_eq!(emulator.args.gas_limit, Some(1000));
+        }
+    }
+
+    #[test]
+    fn restore_keeps_log_sink() {
+        let code = CellBuilder::build_from(0x71_u8).unwrap();
+        let mut emulator = TvmEmulator::new(code, Cell::empty_cell(), 1);
+
+        let make_sink = |rows: &Arc<Mutex<Vec<String>>>| -> LogSink {
+            let rows = rows.clone();
+            Arc::new(move |row: &str| rows.lock().unwrap().push(row.to_owned()))
+        };
+
+        let old_rows = Arc::new(Mutex::new(Vec::new()));
+        emulator.log_sink = Some(make_sink(&old_rows));
+        let snapshot = emulator.snapshot();
+
+        let new_rows = Arc::new(Mutex::new(Vec::new()));
+        emulator.log_sink = Some(make_sink(&new_rows));
+        emulator.restore(&snapshot);
+
+        let subscriber = emulator.make_logger();
+        {
+            let _tracing = tracing::subscriber::set_default(subscriber);
+            emulator.run_get_method(0, Stack::default()).unwrap();
+        }
+
+        assert!(old_rows.lock().unwrap().is_empty());
+        assert!(!new_rows.lock().unwrap().is_empty());
+    }
+
     #[test]
     fn parse_ton_config() {
         let root = Boc::decode(include_bytes!("../res/ton_config.boc")).unwrap();
@@ -1,30 +1,49 @@
 use anyhow::{Context, Result};
-use tycho_types::cell::HashBytes;
 use tycho_types::models::{
-    BlockchainConfig, BlockchainConfigParams, ConfigParam0, GlobalCapability, SizeLimitsConfig,
+    BlockchainConfig, BlockchainConfigParams, BouncePhase, ComputePhase, ConfigParam0,
+    GlobalCapability, IntAddr, LibDescr, MsgForwardPrices, MsgInfo, ShardAccount, SizeLimitsConfig,
+    StdAddr, TickTock, Transaction, TxInfo,
 };
+use tycho_types::num::Tokens;
+use tycho_types::prelude::*;
+use tycho_vm::{SafeRc, SmcInfo, Tuple, VmLogMask};
 
-use crate::subscriber::VmLogSubscriber;
-use crate::util::make_vm_log_mask;
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::state_diff::AccountStateDiff;
+use crate::subscriber::{LogLimits, LogSink, TraceMode, VmLogRows, VmLogSubscriber};
+use crate::util::{UniqueCells, make_vm_log_mask, now_sec_u64, vm_log_capacity};
 
+#[derive(Clone)]
 pub struct TxEmulator {
     pub config: tycho_executor::ParsedConfig,
     pub rand_seed: HashBytes,
-    pub verbosity: i32,
     pub vm_modifiers: tycho_vm::BehaviourModifiers,
+    /// Streams VM log rows instead of buffering them.
+    ///
+    /// Parallel batches are emulated by a single worker when set.
+    pub log_sink: Option<LogSink>,
+    /// Overrides verbosity-based VM log limits.
+    pub log_limits: LogLimits,
 }
 
 impl TxEmulator {
     pub fn new(mut params: BlockchainConfigParams, verbosity: i32) -> Result<Self> {
-        if !params.as_dict().contains_key(43)? {
+        if !params
+            .as_dict()
+            .contains_key(43)
+            .with_kind(ErrorKind::ConfigError)?
+        {
             params
                 .set_size_limits(&DEFAULT_SIZE_LIMITS)
-                .context("Failed to set default size limits (param 43)")?;
+                .context("Failed to set default size limits (param 43)")
+                .with_kind(ErrorKind::ConfigError)?;
         }
 
         let address = params
-            .get::<ConfigParam0>()?
-            .context("Config account address is mandatory in config (param 0)")?;
+            .get::<ConfigParam0>()
+            .with_kind(ErrorKind::ConfigError)?
+            .context("Config account address is mandatory in config (param 0)")
+            .with_kind(ErrorKind::ConfigError)?;
 
         let config = tycho_executor::ParsedConfig::parse(
             BlockchainConfig {
@@ -33,7 +52,8 @@ impl TxEmulator {
             },
             0,
         )
-        .context("Failed to unpack config params")?;
+        .context("Failed to unpack config params")
+        .with_kind(ErrorKind::ConfigError)?;
 
         let capabilities = config.global.capabilities;
         let enable_signature_domains = capabilities.contains(GlobalCapability::CapSignatureDomain);
@@ -44,6 +64,7 @@ impl TxEmulator {
             params
                 .get_global_id()
                 .context("Global id is mandatory (param 19)")
+                .with_kind(ErrorKind::ConfigError)
                 .map(Some)?
         } else {
             None
@@ -52,7 +73,6 @@ impl TxEmulator {
         Ok(Self {
             config,
             rand_seed: HashBytes::ZERO,
-            verbosity,
             vm_modifiers: tycho_vm::BehaviourModifiers {
                 stop_on_accept: false,
                 chksig_always_succeed: false,
@@ -60,19 +80,487 @@ impl TxEmulator {
                 signature_with_id,
                 log_mask: make_vm_log_mask(verbosity, true),
             },
+            log_sink: None,
+            log_limits: LogLimits::default(),
         })
     }
 
+    /// Updates VM log verbosity without rebuilding the config.
+    pub fn set_verbosity(&mut self, verbosity: i32) {
+        self.vm_modifiers.log_mask = make_vm_log_mask(verbosity, true);
+    }
+
     pub fn make_logger(&self) -> VmLogSubscriber {
-        let mut log_max_size = 256;
-        if self.verbosity > 4 {
-            log_max_size = 32 << 20;
-        } else if self.verbosity > 0 {
-            log_max_size = 1 << 20;
+        self.make_logger_with_mask(self.vm_modifiers.log_mask)
+    }
+
+    pub fn make_logger_with_mask(&self, mask: VmLogMask) -> VmLogSubscriber {
+        VmLogSubscriber::new(mask, vm_log_capacity(mask))
+            .with_limits(self.log_limits)
+            .with_sink(self.log_sink.clone())
+    }
+
+    pub fn emulate(
+        &mut self,
+        account: &ShardAccount,
+        input: EmulationInput,
+        params: &EmulationParams,
+    ) -> Result<EmulationOutcome> {
+        let log_mask = params.vm_log_mask.unwrap_or(self.vm_modifiers.log_mask);
+
+        let mut subscriber = self
+            .make_logger_with_mask(log_mask)
+            .with_trace(params.trace);
+        if let Some(limits) = params.log_limits {
+            subscriber = subscriber.with_limits(limits);
+        }
+        let vm_log = subscriber.state().clone();
+        let _tracing = tracing::subscriber::set_default(subscriber);
+
+        let block_unixtime = if params.block_unixtime == 0 {
+            now_sec_u64() as u32
+        } else {
+            params.block_unixtime
+        };
+
+        self.config
+            .update_storage_prices(block_unixtime)
+            .context("Failed to unpack storage prices")
+            .with_kind(ErrorKind::ConfigError)?;
+
+        let msg_info = match &input {
+            EmulationInput::Ordinary(msg) => msg
+                .parse::<MsgInfo>()
+                .context("Failed to unpack message info")
+                .with_kind(ErrorKind::InvalidInput)
+                .map(Some)?,
+            EmulationInput::TickTock(_) => None,
+        };
+        let is_external = matches!(&msg_info, Some(info) if info.is_external_in());
+
+        let address =
+            resolve_address(account, msg_info.as_ref()).with_kind(ErrorKind::InvalidInput)?;
+
+        let executor_params = tycho_executor::ExecutorParams {
+            libraries: params.libraries.clone(),
+            rand_seed: self.rand_seed,
+            block_unixtime,
+            block_lt: params.block_lt,
+            // Will be overwritten by custom hook
+            prev_mc_block_id: None,
+            vm_modifiers: tycho_vm::BehaviourModifiers {
+                chksig_always_succeed: params.ignore_chksig,
+                log_mask: log_mask | params.trace.vm_log_mask(),
+                ..self.vm_modifiers
+            },
+            disable_delete_frozen_accounts: params.disable_delete_frozen_accounts,
+            charge_action_fees_on_fail: params.charge_action_fees_on_fail,
+            full_body_in_bounced: params.full_body_in_bounced,
+            strict_extra_currency: params.strict_extra_currency,
+            authority_marks_enabled: params.authority_marks_enabled,
+        };
+
+        let mut debug_log = String::new();
+        let mut prev_blocks_info = params.prev_blocks_info.clone();
+        let mut c7 = None;
+        let c7_out = params.return_c7.then_some(&mut c7);
+        let mut smc_info_hook = move |smc_info: &mut tycho_executor::phase::ComputePhaseSmcInfo| {
+            smc_info.base.base.prev_blocks_info = prev_blocks_info.take();
+            if let Some(c7_out) = c7_out.as_deref_mut() {
+                *c7_out = Some(smc_info.build_c7());
+            }
+            Ok(())
+        };
+        let mut inspector = tycho_executor::ExecutorInspector {
+            debug: params.debug_enabled.then_some(&mut debug_log),
+            modify_smc_info: Some(&mut smc_info_hook),
+            ..Default::default()
+        };
+
+        let executor = tycho_executor::Executor::new(&executor_params, &self.config)
+            .with_min_lt(executor_params.block_lt);
+        let output = match input {
+            EmulationInput::Ordinary(msg_root) => executor.begin_ordinary_ext(
+                &address,
+                is_external,
+                msg_root,
+                account,
+                Some(&mut inspector),
+            ),
+            EmulationInput::TickTock(ty) => {
+                executor.begin_tick_tock_ext(&address, ty, account, Some(&mut inspector))
+            }
+        };
+
+        let exit_code = inspector.exit_code;
+        let actions = inspector.actions;
+
+        let output = match output {
+            Ok(uncommitted) => uncommitted
+                .commit()
+                .context("Failed to commit transaction")
+                .with_kind(ErrorKind::ExecutorFatal)?,
+            Err(tycho_executor::TxError::Skipped) if is_external => {
+                return Ok(EmulationOutcome::NotAccepted(NotAcceptedMessage {
+                    exit_code: exit_code.unwrap_or(0),
+                    c7,
+                    vm_log,
+                    debug_log,
+                }));
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!("Fatal executor error: {e:?}"))
+                    .with_kind(ErrorKind::ExecutorFatal);
+            }
+        };
+
+        let fees = output
+            .transaction
+            .load()
+            .context("Failed to unpack transaction")
+            .and_then(|tx| self.compute_fees(&tx))?;
+
+        let state_diff =
+            AccountStateDiff::compute(account, &output.new_state, params.data_cells_diff)
+                .context("Failed to compute account state diff")?;
+
+        Ok(EmulationOutcome::Committed(EmulatedTransaction {
+            transaction: output.transaction,
+            fees,
+            state_diff,
+            shard_account: output.new_state,
+            actions,
+            exit_code,
+            c7,
+            vm_log,
+            debug_log,
+        }))
+    }
+
+    /// Collects phase fees of the transaction and forward fees of
+    /// each outbound message using the current config prices.
+    pub fn compute_fees(&self, tx: &Transaction) -> Result<TransactionFees> {
+        let mut fees = TransactionFees {
+            total_fees: tx.total_fees.tokens,
+            ..Default::default()
+        };
+
+        let info = tx
+            .info
+            .load()
+            .context("Failed to unpack transaction info")?;
+        let (storage_phase, compute_phase, action_phase, bounce_phase) = match &info {
+            TxInfo::Ordinary(info) => (
+                info.storage_phase.as_ref(),
+                &info.compute_phase,
+                info.action_phase.as_ref(),
+                info.bounce_phase.as_ref(),
+            ),
+            TxInfo::TickTock(info) => (
+                Some(&info.storage_phase),
+                &info.compute_phase,
+                info.action_phase.as_ref(),
+                None,
+            ),
+        };
+
+        if let Some(phase) = storage_phase {
+            fees.storage_fees_collected = phase.storage_fees_collected;
+            fees.storage_fees_due = phase.storage_fees_due.unwrap_or_default();
+        }
+        if let ComputePhase::Executed(phase) = compute_phase {
+            fees.gas_fees = phase.gas_fees;
+        }
+        if let Some(phase) = action_phase {
+            fees.action_fees = phase.total_action_fees.unwrap_or_default();
+            fees.total_fwd_fees = phase.total_fwd_fees.unwrap_or_default();
+        }
+        let bounce_phase = match bounce_phase {
+            Some(BouncePhase::Executed(phase)) => {
+                fees.bounce_fees =
+                    Tokens::new(phase.msg_fees.into_inner() + phase.fwd_fees.into_inner());
+                Some(phase)
+            }
+            _ => None,
+        };
+        // NOTE: Bounced message is always the last one.
+        let bounced_idx = bounce_phase.map(|_| tx.out_msg_count.into_inner().saturating_sub(1));
+
+        for (i, msg) in tx.out_msgs.values().enumerate() {
+            let msg = msg.context("Invalid outbound messages dict")?;
+
+            // Bounced message is charged by the bounce phase.
+            if let Some(phase) = bounce_phase
+                && bounced_idx == Some(i as _)
+            {
+                fees.out_msgs.push(MessageFees {
+                    hash: *msg.repr_hash(),
+                    fwd_fee: fees.bounce_fees,
+                    action_fee: phase.msg_fees,
+                    remaining_fwd_fee: phase.fwd_fees,
+                });
+                continue;
+            }
+
+            let info = msg
+                .parse::<MsgInfo>()
+                .context("Failed to unpack outbound message info")?;
+
+            let (is_masterchain, remaining_fwd_fee) = match &info {
+                MsgInfo::Int(info) => (
+                    info.src.is_masterchain() || info.dst.is_masterchain(),
+                    info.fwd_fee,
+                ),
+                MsgInfo::ExtOut(info) => (info.src.is_masterchain(), Tokens::ZERO),
+                MsgInfo::ExtIn(_) => anyhow::bail!("Unexpected outbound ext_in message"),
+            };
+
+            let prices = if is_masterchain {
+                &self.config.mc_fwd_prices
+            } else {
+                &self.config.fwd_prices
+            };
+
+            let (cells, bits) = message_size(&msg);
+            let fwd_fee = compute_fwd_fee(prices, cells, bits);
+
+            fees.out_msgs.push(MessageFees {
+                hash: *msg.repr_hash(),
+                fwd_fee,
+                action_fee: Tokens::new(
+                    fwd_fee
+                        .into_inner()
+                        .saturating_sub(remaining_fwd_fee.into_inner()),
+                ),
+                remaining_fwd_fee,
+            });
+        }
+
+        Ok(fees)
+    }
+
+    /// Emulates independent jobs one by one with the same params.
+    pub fn emulate_batch(
+        &mut self,
+        jobs: Vec<EmulationJob>,
+        params: &EmulationParams,
+    ) -> Vec<Result<EmulationOutcome>> {
+        jobs.into_iter()
+            .map(|job| self.emulate(&job.account, job.input, params))
+            .collect()
+    }
+
+    /// Spreads independent jobs over the specified number of worker threads.
+    ///
+    /// Each worker uses its own copy of the emulator and its own params
+    /// built by `make_params`. Results are returned in the order of jobs.
+    ///
+    /// With a log sink jobs are emulated by a single worker in their order,
+    /// so rows of different jobs are never interleaved.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn emulate_batch_parallel<F>(
+        &self,
+        jobs: &[EmulationJob],
+        threads: usize,
+        make_params: F,
+    ) -> Result<Vec<Result<EmulationOutcome>>>
+    where
+        F: Fn() -> Result<EmulationParams> + Sync,
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let threads = match self.log_sink {
+            Some(_) => 1,
+            None => threads.clamp(1, jobs.len().max(1)),
+        };
+        let next_job = AtomicUsize::new(0);
+
+        let worker = || {
+            let mut emulator = self.clone();
+            let params = make_params()?;
+
+            let mut results = Vec::new();
+            loop {
+                let idx = next_job.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(idx) else {
+                    break;
+                };
+
+                let res = emulator.emulate(&job.account, job.input.clone(), &params);
+                results.push((idx, res));
+            }
+            Ok::<_, anyhow::Error>(results)
+        };
+
+        let mut results = std::thread::scope(|s| {
+            let handles = (0..threads).map(|_| s.spawn(&worker)).collect::<Vec<_>>();
+
+            let mut results = Vec::with_capacity(jobs.len());
+            for handle in handles {
+                results.extend(handle.join().expect("emulator worker panicked")?);
+            }
+            Ok::<_, anyhow::Error>(results)
+        })?;
+
+        results.sort_unstable_by_key(|(idx, _)| *idx);
+        Ok(results.into_iter().map(|(_, res)| res).collect())
+    }
+}
+
+/// Independent emulation job of a batch.
+#[derive(Clone)]
+pub struct EmulationJob {
+    pub account: ShardAccount,
+    pub input: EmulationInput,
+}
+
+/// Transaction kind to emulate.
+#[derive(Clone)]
+pub enum EmulationInput {
+    /// Ordinary transaction with an inbound message.
+    Ordinary(Cell),
+    /// Tick-tock transaction of a special account.
+    TickTock(TickTock),
+}
+
+/// Per-call emulation params.
+#[derive(Clone)]
+pub struct EmulationParams {
+    /// Block unixtime. Current time is used when `0`.
+    pub block_unixtime: u32,
+    pub block_lt: u64,
+    pub libraries: Dict<HashBytes, LibDescr>,
+    pub prev_blocks_info: Option<SafeRc<Tuple>>,
+    pub ignore_chksig: bool,
+    pub debug_enabled: bool,
+    pub disable_delete_frozen_accounts: bool,
+    pub charge_action_fees_on_fail: bool,
+    pub full_body_in_bounced: bool,
+    pub strict_extra_currency: bool,
+    pub authority_marks_enabled: bool,
+    /// Whether to compute a cell-level diff of the account data.
+    pub data_cells_diff: bool,
+    /// Whether to capture c7 of the compute phase.
+    pub return_c7: bool,
+    /// Structured trace mode of the compute phase.
+    pub trace: TraceMode,
+    /// Overrides VM log limits of the emulator.
+    pub log_limits: Option<LogLimits>,
+    /// Overrides VM log mask of the emulator.
+    pub vm_log_mask: Option<VmLogMask>,
+}
+
+impl Default for EmulationParams {
+    fn default() -> Self {
+        Self {
+            block_unixtime: 0,
+            block_lt: 0,
+            libraries: Dict::new(),
+            prev_blocks_info: None,
+            ignore_chksig: false,
+            debug_enabled: false,
+            disable_delete_frozen_accounts: true,
+            charge_action_fees_on_fail: true,
+            full_body_in_bounced: false,
+            strict_extra_currency: true,
+            authority_marks_enabled: false,
+            data_cells_diff: false,
+            return_c7: false,
+            trace: TraceMode::Disabled,
+            log_limits: None,
+            vm_log_mask: None,
         }
+    }
+}
+
+pub enum EmulationOutcome {
+    /// Transaction was successfully executed and committed.
+    Committed(EmulatedTransaction),
+    /// External message was not accepted by the contract.
+    NotAccepted(NotAcceptedMessage),
+}
+
+pub struct EmulatedTransaction {
+    pub transaction: Lazy<Transaction>,
+    pub fees: TransactionFees,
+    pub state_diff: AccountStateDiff,
+    pub shard_account: ShardAccount,
+    pub actions: Option<Cell>,
+    pub exit_code: Option<i32>,
+    /// c7 of the compute phase (if requested).
+    pub c7: Option<SafeRc<Tuple>>,
+    pub vm_log: VmLogRows,
+    pub debug_log: String,
+}
+
+/// Fees charged by the transaction.
+#[derive(Default, Clone)]
+pub struct TransactionFees {
+    pub storage_fees_collected: Tokens,
+    pub storage_fees_due: Tokens,
+    pub gas_fees: Tokens,
+    pub action_fees: Tokens,
+    pub total_fwd_fees: Tokens,
+    /// Fees for the bounced message (if any).
+    pub bounce_fees: Tokens,
+    pub total_fees: Tokens,
+    /// Fees of outbound messages in the order of their indices.
+    pub out_msgs: Vec<MessageFees>,
+}
+
+/// Forward fees of an outbound message.
+#[derive(Clone)]
+pub struct MessageFees {
+    pub hash: HashBytes,
+    /// Full forward fee computed from the message size.
+    pub fwd_fee: Tokens,
+    /// Part of the forward fee collected in the action phase
+    /// (or in the bounce phase for the bounced message).
+    pub action_fee: Tokens,
+    /// Part of the forward fee attached to the message (`fwd_fee` field).
+    pub remaining_fwd_fee: Tokens,
+}
+
+pub struct NotAcceptedMessage {
+    pub exit_code: i32,
+    /// c7 of the compute phase (if requested).
+    pub c7: Option<SafeRc<Tuple>>,
+    pub vm_log: VmLogRows,
+    pub debug_log: String,
+}
+
+fn resolve_address(account: &ShardAccount, msg_info: Option<&MsgInfo>) -> Result<StdAddr> {
+    let address = match account.load_account().context("Failed to unpack account")? {
+        Some(account) => account.address,
+        None => match msg_info {
+            Some(MsgInfo::Int(info)) => info.dst.clone(),
+            Some(MsgInfo::ExtIn(info)) => info.dst.clone(),
+            Some(MsgInfo::ExtOut(_)) => {
+                anyhow::bail!("Only internal and external inbound messages are accepted");
+            }
+            None => anyhow::bail!("Can't run tick-tock transaction on account_none"),
+        },
+    };
+
+    match address {
+        IntAddr::Std(address) => Ok(address),
+        IntAddr::Var(_) => anyhow::bail!("var_addr is not supported"),
+    }
+}
 
-        VmLogSubscriber::new(self.vm_modifiers.log_mask, log_max_size)
+/// Computes the number of unique cells and bits of the message without its root.
+fn message_size(msg: &DynCell) -> (u64, u64) {
+    let mut unique = UniqueCells::default();
+    for child in msg.references() {
+        unique.visit(child);
     }
+    (unique.cells(), unique.bits)
+}
+
+fn compute_fwd_fee(prices: &MsgForwardPrices, cells: u64, bits: u64) -> Tokens {
+    let size_fee = (prices.bit_price as u128 * bits as u128)
+        .saturating_add(prices.cell_price as u128 * cells as u128);
+    Tokens::new((prices.lump_price as u128).saturating_add(size_fee.div_ceil(1 << 16)))
 }
 
 static DEFAULT_SIZE_LIMITS: SizeLimitsConfig = SizeLimitsConfig {
@@ -90,9 +578,12 @@ static DEFAULT_SIZE_LIMITS: SizeLimitsConfig = SizeLimitsConfig {
 
 #[cfg(test)]
 mod tests {
-    use tycho_types::prelude::*;
+    use tycho_types::models::OptionalAccount;
 
     use super::*;
+    use crate::test_utils::{
+        SEND_ONE, THROW, addr, int_msg, make_account, make_emulator, make_params,
+    };
 
     #[test]
     fn parse_tycho_config() {
@@ -107,4 +598,147 @@ mod tests {
         let params = BlockchainConfigParams::from_raw(root);
         TxEmulator::new(params, 0).unwrap();
     }
+
+    #[test]
+    fn emulate_internal_to_empty_account() {
+        let mut emulator = make_emulator();
+
+        let account = ShardAccount {
+            account: Lazy::new(&OptionalAccount::EMPTY).unwrap(),
+            last_trans_hash: HashBytes::ZERO,
+            last_trans_lt: 0,
+        };
+
+        let msg = int_msg(&addr(1), &addr(2), false, &[]);
+
+        let params = EmulationParams {
+            block_lt: 1000,
+            ..Default::default()
+        };
+        let outcome = emulator
+            .emulate(&account, EmulationInput::Ordinary(msg), &params)
+            .unwrap();
+
+        let EmulationOutcome::Committed(tx) = outcome else {
+            panic!("transaction must be committed");
+        };
+        assert!(tx.fees.out_msgs.is_empty());
+
+        let fees = tx.fees;
+        let tx = tx.transaction.load().unwrap();
+        assert!(tx.lt >= 1000);
+        assert_eq!(fees.total_fees, tx.total_fees.tokens);
+    }
+
+    #[test]
+    fn emulate_batch_keeps_order() {
+        let mut emulator = make_emulator();
+
+        let account = crate::local_shard::empty_shard_account().unwrap();
+        let jobs = (1..=5u8)
+            .map(|i| {
+                let msg = int_msg(&addr(0xff), &addr(i), false, &[]);
+
+                EmulationJob {
+                    account: account.clone(),
+                    input: EmulationInput::Ordinary(msg),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let params = make_params();
+        let sequential = emulator.emulate_batch(jobs.clone(), &params);
+        let parallel = emulator
+            .emulate_batch_parallel(&jobs, 3, || Ok(params.clone()))
+            .unwrap();
+        assert_eq!(sequential.len(), jobs.len());
+        assert_eq!(parallel.len(), jobs.len());
+
+        for (i, (seq, par)) in sequential.into_iter().zip(parallel).enumerate() {
+            let (Ok(EmulationOutcome::Committed(seq)), Ok(EmulationOutcome::Committed(par))) =
+                (seq, par)
+            else {
+                panic!("transactions must be committed");
+            };
+
+            let tx = seq.transaction.load().unwrap();
+            assert_eq!(tx.account, HashBytes([i as u8 + 1; 32]));
+            assert_eq!(
+                seq.transaction.inner().repr_hash(),
+                par.transaction.inner().repr_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn out_message_fees() {
+        let address = addr(1);
+        let account = make_account(&address, SEND_ONE);
+
+        // Outbound message with a non-trivial body to make its size matter.
+        let mut payload = CellBuilder::new();
+        payload.store_u64(0xdeadbeef).unwrap();
+        payload
+            .store_reference(CellBuilder::build_from(0xcafeu32).unwrap())
+            .unwrap();
+        let out_msg = int_msg(&address, &addr(2), false, &[payload.build().unwrap()]);
+        let msg = int_msg(&addr(0xff), &address, false, &[out_msg]);
+
+        let outcome = make_emulator()
+            .emulate(&account, EmulationInput::Ordinary(msg), &make_params())
+            .unwrap();
+        let EmulationOutcome::Committed(tx) = outcome else {
+            panic!("transaction must be committed");
+        };
+
+        let fees = tx.fees;
+        let [msg_fees] = fees.out_msgs.as_slice() else {
+            panic!("one outbound message expected");
+        };
+        assert!(msg_fees.fwd_fee > Tokens::ZERO);
+        assert_eq!(
+            msg_fees.fwd_fee.into_inner(),
+            msg_fees.action_fee.into_inner() + msg_fees.remaining_fwd_fee.into_inner()
+        );
+
+        // Per-message fees must add up to the action phase totals.
+        let total_fwd_fees = fees
+            .out_msgs
+            .iter()
+            .map(|fees| fees.fwd_fee.into_inner())
+            .sum::<u128>();
+        let total_action_fees = fees
+            .out_msgs
+            .iter()
+            .map(|fees| fees.action_fee.into_inner())
+            .sum::<u128>();
+        assert_eq!(total_fwd_fees, fees.total_fwd_fees.into_inner());
+        assert_eq!(total_action_fees, fees.action_fees.into_inner());
+    }
+
+    #[test]
+    fn bounced_message_fees() {
+        let address = addr(1);
+        let account = make_account(&address, THROW);
+        let msg = int_msg(&addr(0xff), &address, true, &[]);
+
+        let outcome = make_emulator()
+            .emulate(&account, EmulationInput::Ordinary(msg), &make_params())
+            .unwrap();
+        let EmulationOutcome::Committed(tx) = outcome else {
+            panic!("transaction must be committed");
+        };
+
+        let fees = tx.fees;
+        let [msg_fees] = fees.out_msgs.as_slice() else {
+            panic!("bounced message expected");
+        };
+        assert_eq!(fees.action_fees, Tokens::ZERO);
+        assert!(fees.bounce_fees > Tokens::ZERO);
+        assert_eq!(msg_fees.fwd_fee, fees.bounce_fees);
+        assert_eq!(
+            msg_fees.fwd_fee.into_inner(),
+            msg_fees.action_fee.into_inner() + msg_fees.remaining_fwd_fee.into_inner()
+        );
+    }
 }
@@ -1,10 +1,18 @@
 use std::borrow::Cow;
 use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::Deserialize;
 use serde::de::Error;
-use tycho_vm::VmLogMask;
+use tycho_types::models::{
+    Account, AccountState, CurrencyCollection, OptionalAccount, ShardAccount, StdAddr, StorageInfo,
+    StorageUsed,
+};
+use tycho_types::num::VarUint56;
+use tycho_types::prelude::*;
+use tycho_vm::{SafeRc, Stack, Tuple, VmLogMask};
+
+use crate::error::{ErrorKind, WithErrorKind};
 
 #[cfg(target_arch = "wasm32")]
 pub fn now_sec_u64() -> u64 {
@@ -44,6 +52,111 @@ pub fn make_vm_log_mask(verbosity: i32, allow_c5: bool) -> VmLogMask {
     res
 }
 
+/// Explicit VM log mask bits take precedence over verbosity.
+pub fn resolve_vm_log_mask(mask: Option<u32>, verbosity: Option<i32>) -> Option<VmLogMask> {
+    match (mask, verbosity) {
+        (Some(bits), _) => Some(VmLogMask::from_bits_truncate(bits as _)),
+        (None, Some(verbosity)) => Some(make_vm_log_mask(verbosity, true)),
+        (None, None) => None,
+    }
+}
+
+/// Default VM log capacity (in rows) for the specified mask.
+pub fn vm_log_capacity(mask: VmLogMask) -> usize {
+    if mask.contains(VmLogMask::DUMP_STACK_VERBOSE) {
+        32 << 20
+    } else if mask.contains(VmLogMask::MESSAGE) {
+        1 << 20
+    } else {
+        256
+    }
+}
+
+pub fn parse_prev_blocks_info(prev_blocks_info: Option<&Cell>) -> Result<Option<SafeRc<Tuple>>> {
+    Ok(if let Some(prev_blocks) = prev_blocks_info {
+        let info_value = Stack::load_stack_value_from_cell(prev_blocks.as_ref())
+            .context("Failed to deserialize previous blocks tuple")
+            .with_kind(ErrorKind::InvalidInput)?;
+
+        if info_value.is_null() {
+            None
+        } else if let Ok(tuple) = info_value.into_tuple() {
+            Some(tuple)
+        } else {
+            return Err(anyhow::anyhow!(
+                "Failed to set previous blocks tuple: not a tuple"
+            ))
+            .with_kind(ErrorKind::InvalidInput);
+        }
+    } else {
+        None
+    })
+}
+
+/// Unique cells of one or more cell trees.
+#[derive(Default)]
+pub struct UniqueCells {
+    pub hashes: ahash::HashSet<HashBytes>,
+    pub bits: u64,
+}
+
+impl UniqueCells {
+    /// Visits all cells of the tree (including its root) which were not visited yet.
+    pub fn visit(&mut self, root: &DynCell) {
+        let mut stack = vec![root];
+        while let Some(cell) = stack.pop() {
+            if self.hashes.insert(*cell.repr_hash()) {
+                self.bits += cell.bit_len() as u64;
+                stack.extend(cell.references());
+            }
+        }
+    }
+
+    pub fn cells(&self) -> u64 {
+        self.hashes.len() as u64
+    }
+}
+
+/// Builds a shard account with the storage stats of its state.
+///
+/// Storage stats are computed over the unique cells of the account storage
+/// (`last_trans_lt`, `balance` and `state`) including its root.
+pub fn make_shard_account(
+    address: StdAddr,
+    balance: CurrencyCollection,
+    state: AccountState,
+    last_paid: u32,
+    last_trans_lt: u64,
+) -> Result<ShardAccount> {
+    let storage = CellBuilder::build_from((last_trans_lt, &balance, &state))
+        .context("Failed to build account storage")?;
+
+    let mut unique = UniqueCells::default();
+    unique.visit(storage.as_ref());
+
+    let account = Account {
+        address: address.into(),
+        storage_stat: StorageInfo {
+            used: StorageUsed {
+                cells: VarUint56::new(unique.cells()),
+                bits: VarUint56::new(unique.bits),
+            },
+            last_paid,
+            due_payment: None,
+            ..Default::default()
+        },
+        last_trans_lt,
+        balance,
+        state,
+    };
+
+    Ok(ShardAccount {
+        account: Lazy::new(&OptionalAccount(Some(account)))?,
+        last_trans_hash: HashBytes::ZERO,
+        last_trans_lt,
+    })
+}
+
 #[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
 pub struct JsonBool<const VALUE: bool>;
 
@@ -88,6 +201,20 @@ pub mod serde_extra_currencies {
 
         ExtraCurrencyCollection::try_from(items).map_err(Error::custom)
     }
+
+    pub fn serialize<S>(value: &ExtraCurrencyCollection, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error;
+
+        let mut items = std::collections::BTreeMap::new();
+        for item in value.as_dict().iter() {
+            let (id, value) = item.map_err(Error::custom)?;
+            items.insert(id.to_string(), value);
+        }
+        serializer.collect_map(items)
+    }
 }
 
 pub mod serde_ton_address {
@@ -129,6 +256,32 @@ pub mod serde_string {
     }
 }
 
+pub mod serde_optional_string {
+    use super::*;
+
+    pub fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        T: std::fmt::Display,
+    {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        Option::<BorrowedStr>::deserialize(deserializer)?
+            .map(|data| T::from_str(&data.0).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
 #[derive(Deserialize)]
 #[repr(transparent)]
 pub struct BorrowedStr<'a>(#[serde(borrow)] pub Cow<'a, str>);
@@ -1,21 +1,26 @@
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
 use anyhow::{Context, Result};
 use tycho_types::models::{
-    BlockchainConfigParams, IntAddr, LibDescr, MsgInfo, ShardAccount, SimpleLib, TickTock,
+    BlockchainConfigParams, LibDescr, ShardAccount, SimpleLib, StdAddr, TickTock,
 };
 use tycho_types::prelude::*;
 use tycho_vm::Stack;
 use wasm_bindgen::prelude::*;
 
+use crate::error::{ErrorKind, WithErrorKind};
+use crate::json_stack::parse_stack;
+use crate::local_shard::LocalShard;
 use crate::models::{
-    EmulatorParams, ErrResponse, OkResponse, RunGetMethodParams, RunGetMethodResponse,
-    TxEmulatorErrorResponse, TxEmulatorMsgNotAcceptedResponse, TxEmulatorResponse,
-    TxEmulatorSuccessResponse, VersionInfo,
+    AccountGetMethodParams, EmulationJobParams, EmulatorParams, ErrResponse, GetMethodCall,
+    InternalMessageParams, LocalShardResponse, OkResponse, RunGetMethodErrResponse,
+    RunGetMethodParams, RunGetMethodResponse, ShardAccountParams, StackFormat,
+    TvmEmulatorSendMessageResponse, TxEmulatorErrorResponse, TxEmulatorResponse, VersionInfo,
 };
-use crate::tvm_emulator::{self, TvmEmulator};
-use crate::tx_emulator::TxEmulator;
-use crate::util::{JsonBool, now_sec_u64};
+use crate::subscriber::{LogLimits, LogSink};
+use crate::tvm_emulator::{self, TvmEmulator, TvmEmulatorSnapshot};
+use crate::tx_emulator::{EmulationInput, EmulationJob, EmulationParams, TxEmulator};
+use crate::util::{JsonBool, parse_prev_blocks_info};
 use crate::{EMULATOR_COMMIT_DATE, EMULATOR_COMMIT_HASH};
 
 // === Exported Methods ===
@@ -36,7 +41,7 @@ pub fn version() -> js_sys::JsString {
 #[wasm_bindgen]
 pub fn create_emulator(config: &str, verbosity: i32) -> Result<*mut TxEmulator, JsError> {
     (|| {
-        let config = Boc::decode_base64(config)?;
+        let config = Boc::decode_base64(config).with_kind(ErrorKind::ConfigError)?;
         let emulator = TxEmulator::new(BlockchainConfigParams::from_raw(config), verbosity)?;
         Ok::<_, anyhow::Error>(Box::into_raw(Box::new(emulator)))
     })()
@@ -54,6 +59,43 @@ pub fn destroy_emulator(transaction_emulator: *mut TxEmulator) -> Result<(), JsE
     Ok(())
 }
 
+/// Overrides verbosity-based VM log limits with JSON `LogLimits`.
+///
+/// Passing `undefined` restores the defaults.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn emulator_set_log_limits(
+    transaction_emulator: *mut TxEmulator,
+    limits: Option<String>,
+) -> Result<(), JsError> {
+    if transaction_emulator.is_null() {
+        return Err(JsError::new("transaction_emulator is null"));
+    }
+
+    let limits = parse_log_limits(limits.as_deref()).map_err(|e| JsError::new(&e.to_string()))?;
+    let emulator = unsafe { &mut *transaction_emulator };
+    emulator.log_limits = limits;
+    Ok(())
+}
+
+/// Streams VM log rows to `callback(row)` instead of buffering them.
+///
+/// Passing `undefined` returns to the buffered log.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn emulator_set_log_callback(
+    transaction_emulator: *mut TxEmulator,
+    callback: Option<js_sys::Function>,
+) -> Result<(), JsError> {
+    if transaction_emulator.is_null() {
+        return Err(JsError::new("transaction_emulator is null"));
+    }
+
+    let emulator = unsafe { &mut *transaction_emulator };
+    emulator.log_sink = callback.map(make_js_log_sink);
+    Ok(())
+}
+
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 #[wasm_bindgen]
 pub fn emulate_with_emulator(
@@ -64,19 +106,24 @@ pub fn emulate_with_emulator(
     params: &str,
 ) -> js_sys::JsString {
     // Parse input params.
-    let (emulator, params, libraries, mut prev_blocks_info) = match (|| {
+    let (emulator, params, libraries, prev_blocks_info) = match (|| {
         anyhow::ensure!(!emulator.is_null(), "emulator pointer is null");
         let emulator = unsafe { &mut *emulator };
 
-        let params: EmulatorParams =
-            serde_json::from_str(params).context("Failed to parse emulator params")?;
-        anyhow::ensure!(
-            params.is_tick_tock || !params.is_tock,
-            "Inconsistent parameters: is_tick_tock=false, is_tock=true"
-        );
+        let params: EmulatorParams = serde_json::from_str(params)
+            .context("Failed to parse emulator params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        if !params.is_tick_tock && params.is_tock {
+            return Err(anyhow::anyhow!(
+                "Inconsistent parameters: is_tick_tock=false, is_tock=true"
+            ))
+            .with_kind(ErrorKind::InvalidInput);
+        }
 
         let libraries = if let Some(libs) = libs {
-            let root = Boc::decode_base64(libs).context("Failed to deserialize libraries")?;
+            let root = Boc::decode_base64(libs)
+                .context("Failed to deserialize libraries")
+                .with_kind(ErrorKind::InvalidInput)?;
             emulator_libs_to_shard(root)?
         } else {
             Dict::new()
@@ -92,6 +139,7 @@ pub fn emulate_with_emulator(
         Err(e) => {
             let value = serde_json::to_string(&ErrResponse {
                 message: format!("{e:?}"),
+                error_kind: ErrorKind::of(&e),
             })
             .unwrap();
             return JsValue::from(value).unchecked_into();
@@ -99,159 +147,203 @@ pub fn emulate_with_emulator(
     };
 
     (move || {
-        let subscriber = emulator.make_logger();
-        let vm_log = subscriber.state().clone();
-        let _tracing = tracing::subscriber::set_default(subscriber);
+        let account = parse_shard_account(account)?;
+        let input = parse_emulation_input(message, &params).with_kind(ErrorKind::InvalidInput)?;
 
-        // Parse accounts and messages.
-        let is_tock = params.is_tock;
-        let unixtime = if params.unixtime == 0 {
-            now_sec_u64() as u32
-        } else {
-            params.unixtime
-        };
+        if let Some(rand_seed) = params.rand_seed {
+            emulator.rand_seed = rand_seed;
+        }
 
-        emulator
-            .config
-            .update_storage_prices(unixtime)
-            .context("Failed to unpack storage prices")?;
-
-        let account = Boc::decode_base64(account)
-            .context("Failed to deserialize shard account")?
-            .parse::<ShardAccount>()
-            .context("Failed to unpack shard account")?;
-
-        let is_external;
-        let message = if let Some(msg) = message {
-            anyhow::ensure!(
-                !params.is_tick_tock,
-                "Tick-tock transactions cannot have an inbound message"
-            );
-
-            let msg = Boc::decode_base64(msg).context("Failed to deserialize message")?;
-            let msg_info = msg
-                .parse::<MsgInfo>()
-                .context("Failed to unpack message info")?;
-
-            is_external = msg_info.is_external_in();
-            Some((msg, msg_info))
+        let params = make_emulation_params(&params, libraries, prev_blocks_info);
+        let res = TxEmulatorResponse::try_from(emulator.emulate(&account, input, &params)?)?;
+
+        let res = serde_json::to_string(&OkResponse { output: res }).unwrap();
+        Ok::<_, anyhow::Error>(JsValue::from(res).unchecked_into())
+    })()
+    .unwrap_or_else(|e| {
+        let res = serde_json::to_string(&OkResponse {
+            output: TxEmulatorResponse::Error(TxEmulatorErrorResponse {
+                success: JsonBool,
+                error: e.to_string(),
+                error_kind: ErrorKind::of(&e),
+                external_not_accepted: JsonBool,
+                debug_log: String::new(),
+            }),
+        })
+        .unwrap();
+        JsValue::from(res).unchecked_into()
+    })
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn emulate_batch_with_emulator(
+    emulator: *mut TxEmulator,
+    libs: Option<String>,
+    jobs: &str,
+    params: &str,
+) -> js_sys::JsString {
+    (|| {
+        anyhow::ensure!(!emulator.is_null(), "emulator pointer is null");
+        let emulator = unsafe { &mut *emulator };
+
+        let params: EmulatorParams = serde_json::from_str(params)
+            .context("Failed to parse emulator params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let jobs: Vec<EmulationJobParams> = serde_json::from_str(jobs)
+            .context("Failed to parse emulation jobs")
+            .with_kind(ErrorKind::InvalidInput)?;
+
+        let libraries = if let Some(libs) = libs {
+            let root = Boc::decode_base64(libs)
+                .context("Failed to deserialize libraries")
+                .with_kind(ErrorKind::InvalidInput)?;
+            emulator_libs_to_shard(root)?
         } else {
-            is_external = false;
-            anyhow::ensure!(
-                params.is_tick_tock,
-                "Inbound message is required for ordinary transactions"
-            );
-            None
+            Dict::new()
         };
 
-        let IntAddr::Std(address) =
-            (match account.load_account().context("Failed to unpack account")? {
-                Some(account) => account.address,
-                None => match message.as_ref().map(|(_, info)| info) {
-                    Some(MsgInfo::Int(info)) => info.dst.clone(),
-                    Some(MsgInfo::ExtIn(info)) => info.dst.clone(),
-                    Some(MsgInfo::ExtOut(_)) => {
-                        anyhow::bail!("Only internal and external inbound messages are accepted");
-                    }
-                    None => anyhow::bail!("Can't run tick-tock transaction on account_none"),
-                },
-            })
-        else {
-            anyhow::bail!("var_addr is not supported");
-        };
+        let prev_blocks_info = parse_prev_blocks_info(params.prev_blocks_info.as_ref())?;
 
         if let Some(rand_seed) = params.rand_seed {
             emulator.rand_seed = rand_seed;
         }
 
-        let debug_enabled = params.debug_enabled;
-        let params = tycho_executor::ExecutorParams {
-            libraries,
-            rand_seed: emulator.rand_seed,
-            block_unixtime: unixtime,
-            block_lt: params.lt,
-            // Will be overwritten by custom hook
-            prev_mc_block_id: None,
-            vm_modifiers: tycho_vm::BehaviourModifiers {
-                chksig_always_succeed: params.ignore_chksig,
-                ..emulator.vm_modifiers
-            },
-            disable_delete_frozen_accounts: params.disable_delete_frozen_accounts.unwrap_or(true),
-            charge_action_fees_on_fail: params.charge_action_fees_on_fail.unwrap_or(true),
-            full_body_in_bounced: params.full_body_in_bounced.unwrap_or(false),
-            strict_extra_currency: params.strict_extra_currency.unwrap_or(true),
-            authority_marks_enabled: params.authority_marks_enabled.unwrap_or(false),
-        };
+        let params = make_emulation_params(&params, libraries, prev_blocks_info);
+        let jobs = jobs.into_iter().map(EmulationJob::from).collect();
 
-        let mut debug_log = String::new();
-        let mut smc_info_hook = move |smc_info: &mut tycho_executor::phase::ComputePhaseSmcInfo| {
-            smc_info.base.base.prev_blocks_info = prev_blocks_info.take();
-            Ok(())
-        };
-        let mut inspector = tycho_executor::ExecutorInspector {
-            debug: debug_enabled.then_some(&mut debug_log),
-            modify_smc_info: Some(&mut smc_info_hook),
-            ..Default::default()
-        };
+        let res = emulator
+            .emulate_batch(jobs, &params)
+            .into_iter()
+            .map(TxEmulatorResponse::from_result)
+            .collect::<Vec<_>>();
 
-        let output = match message {
-            Some((msg_root, _)) => tycho_executor::Executor::new(&params, &emulator.config)
-                .with_min_lt(params.block_lt)
-                .begin_ordinary_ext(
-                    &address,
-                    is_external,
-                    msg_root,
-                    &account,
-                    Some(&mut inspector),
-                ),
-            None => {
-                let ty = if is_tock {
-                    TickTock::Tock
-                } else {
-                    TickTock::Tick
-                };
-                tycho_executor::Executor::new(&params, &emulator.config)
-                    .with_min_lt(params.block_lt)
-                    .begin_tick_tock_ext(&address, ty, &account, Some(&mut inspector))
-            }
-        };
+        let res = serde_json::to_string(&OkResponse { output: res }).unwrap();
+        Ok::<_, anyhow::Error>(JsValue::from(res).unchecked_into())
+    })()
+    .unwrap_or_else(|e| {
+        let value = serde_json::to_string(&ErrResponse {
+            message: format!("{e:?}"),
+            error_kind: ErrorKind::of(&e),
+        })
+        .unwrap();
+        JsValue::from(value).unchecked_into()
+    })
+}
 
-        let res = 'res: {
-            let output = match output {
-                Ok(uncommitted) => uncommitted
-                    .commit()
-                    .context("Failed to commit transaction")?,
-                Err(tycho_executor::TxError::Skipped) if is_external => {
-                    break 'res TxEmulatorResponse::NotAccepted(TxEmulatorMsgNotAcceptedResponse {
-                        success: JsonBool,
-                        error: "External message not accepted by smart contract",
-                        external_not_accepted: JsonBool,
-                        vm_log,
-                        vm_exit_code: inspector.exit_code.unwrap_or(0),
-                        debug_log,
-                    });
-                }
-                Err(e) => anyhow::bail!("Fatal executor error: {e:?}"),
-            };
-
-            TxEmulatorResponse::Success(TxEmulatorSuccessResponse {
-                success: JsonBool,
-                transaction: output.transaction.into_inner(),
-                shard_account: output.new_state,
-                vm_log,
-                actions: inspector.actions,
-                debug_log,
-            })
+#[wasm_bindgen]
+pub fn create_local_shard(max_transactions: u32) -> *mut LocalShard {
+    let mut shard = LocalShard::new();
+    if max_transactions > 0 {
+        shard = shard.with_max_transactions(max_transactions as usize);
+    }
+    Box::into_raw(Box::new(shard))
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn destroy_local_shard(local_shard: *mut LocalShard) -> Result<(), JsError> {
+    if local_shard.is_null() {
+        return Err(JsError::new("local_shard is null"));
+    }
+
+    _ = unsafe { Box::from_raw(local_shard) };
+    Ok(())
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn local_shard_set_account(
+    local_shard: *mut LocalShard,
+    address: &str,
+    account: &str,
+) -> Result<(), JsError> {
+    (|| {
+        anyhow::ensure!(!local_shard.is_null(), "local_shard pointer is null");
+        let local_shard = unsafe { &mut *local_shard };
+
+        let address = address.parse::<StdAddr>().context("Invalid address")?;
+        let account = parse_shard_account(account)?;
+
+        local_shard.set_account(address, account);
+        Ok::<_, anyhow::Error>(())
+    })()
+    .map_err(|e| JsError::new(&e.to_string()))
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn local_shard_get_account(
+    local_shard: *mut LocalShard,
+    address: &str,
+) -> Result<Option<String>, JsError> {
+    (|| {
+        anyhow::ensure!(!local_shard.is_null(), "local_shard pointer is null");
+        let local_shard = unsafe { &*local_shard };
+
+        let address = address.parse::<StdAddr>().context("Invalid address")?;
+        match local_shard.get_account(&address) {
+            Some(account) => Ok(Some(BocRepr::encode_base64(account)?)),
+            None => Ok(None),
+        }
+    })()
+    .map_err(|e: anyhow::Error| JsError::new(&e.to_string()))
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn local_shard_execute(
+    local_shard: *mut LocalShard,
+    emulator: *mut TxEmulator,
+    libs: Option<String>,
+    message: &str,
+    params: &str,
+) -> js_sys::JsString {
+    (|| {
+        anyhow::ensure!(!local_shard.is_null(), "local_shard pointer is null");
+        let local_shard = unsafe { &mut *local_shard };
+        anyhow::ensure!(!emulator.is_null(), "emulator pointer is null");
+        let emulator = unsafe { &mut *emulator };
+
+        let params: EmulatorParams = serde_json::from_str(params)
+            .context("Failed to parse emulator params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        if params.is_tick_tock {
+            return Err(anyhow::anyhow!("Tick-tock transactions cannot be routed"))
+                .with_kind(ErrorKind::InvalidInput);
+        }
+
+        let libraries = if let Some(libs) = libs {
+            let root = Boc::decode_base64(libs)
+                .context("Failed to deserialize libraries")
+                .with_kind(ErrorKind::InvalidInput)?;
+            emulator_libs_to_shard(root)?
+        } else {
+            Dict::new()
         };
 
+        let prev_blocks_info = parse_prev_blocks_info(params.prev_blocks_info.as_ref())?;
+
+        let message = Boc::decode_base64(message)
+            .context("Failed to deserialize message")
+            .with_kind(ErrorKind::InvalidInput)?;
+
+        if let Some(rand_seed) = params.rand_seed {
+            emulator.rand_seed = rand_seed;
+        }
+
+        let params = make_emulation_params(&params, libraries, prev_blocks_info);
+        let res = LocalShardResponse::try_from(local_shard.execute(emulator, message, &params)?)?;
+
         let res = serde_json::to_string(&OkResponse { output: res }).unwrap();
         Ok::<_, anyhow::Error>(JsValue::from(res).unchecked_into())
     })()
     .unwrap_or_else(|e| {
         let res = serde_json::to_string(&OkResponse {
-            output: TxEmulatorResponse::Error(TxEmulatorErrorResponse {
+            output: LocalShardResponse::Error(TxEmulatorErrorResponse {
                 success: JsonBool,
                 error: e.to_string(),
+                error_kind: ErrorKind::of(&e),
                 external_not_accepted: JsonBool,
                 debug_log: String::new(),
             }),
@@ -261,61 +353,301 @@ pub fn emulate_with_emulator(
     })
 }
 
+#[wasm_bindgen]
+pub fn build_shard_account(params: &str) -> Result<String, JsError> {
+    (|| {
+        let params: ShardAccountParams =
+            serde_json::from_str(params).context("Failed to parse account params")?;
+        let account = params.build()?;
+        Ok(BocRepr::encode_base64(account)?)
+    })()
+    .map_err(|e: anyhow::Error| JsError::new(&e.to_string()))
+}
+
 #[wasm_bindgen]
 pub fn run_get_method(params: &str, stack: &str, config: &str) -> js_sys::JsString {
+    let mut resolved_method_id = None;
     (|| {
-        let params: RunGetMethodParams =
-            serde_json::from_str(params).context("Can't decode params")?;
+        let params: RunGetMethodParams = serde_json::from_str(params)
+            .context("Can't decode params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let method_id = params.base.resolve_method_id()?;
+        resolved_method_id = Some(method_id);
 
-        let stack = Boc::decode_base64(stack).context("Failed to deserialize stack cell")?;
-        let stack = stack
-            .parse::<Stack>()
-            .context("Failed to deserialize stack")?;
+        let stack = parse_stack(stack)?;
 
-        let config = Boc::decode_base64(config).context("Failed to deserialize config cell")?;
-        let config = tvm_emulator::ParsedConfig::try_from_root(config)
-            .context("Failed to deserialize config")?;
+        let stack_format = params.base.stack_format;
+        let emulator = make_get_method_emulator(params, config)?;
 
-        let prev_blocks = parse_prev_blocks_info(params.prev_blocks_info.as_ref())?;
+        run_get_method_impl(&emulator, method_id, stack, stack_format)
+    })()
+    .unwrap_or_else(|e| make_get_method_error(e, resolved_method_id))
+}
 
-        let mut emulator = TvmEmulator::new(params.code, params.data, params.verbosity);
+/// Runs multiple get-methods against the same state.
+///
+/// Method fields of `params` are ignored, `gas_limit` and `stack_format`
+/// are used as defaults for each call.
+#[wasm_bindgen]
+pub fn run_get_methods(params: &str, calls: &str, config: &str) -> js_sys::JsString {
+    (|| {
+        let params: RunGetMethodParams = serde_json::from_str(params)
+            .context("Can't decode params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let calls: Vec<GetMethodCall> = serde_json::from_str(calls)
+            .context("Can't decode get-method calls")
+            .with_kind(ErrorKind::InvalidInput)?;
+
+        let stack_format = params.base.stack_format;
+        let mut emulator = make_get_method_emulator(params, config)?;
+
+        let output = calls
+            .into_iter()
+            .map(|call| call.run(&mut emulator, stack_format))
+            .collect::<Vec<_>>();
+
+        let res = serde_json::to_string(&OkResponse { output }).unwrap();
+        Ok::<_, anyhow::Error>(JsValue::from(res).unchecked_into())
+    })()
+    .unwrap_or_else(|e| {
+        let value = serde_json::to_string(&ErrResponse {
+            message: e.to_string(),
+            error_kind: ErrorKind::of(&e),
+        })
+        .unwrap();
+
+        JsValue::from(value).unchecked_into()
+    })
+}
+
+fn make_get_method_emulator(params: RunGetMethodParams, config: &str) -> Result<TvmEmulator> {
+    let mut emulator = TvmEmulator::new(params.code, params.data, params.base.verbosity);
+    emulator.args.address = Some(params.address);
+    emulator.args.balance = params.balance;
+    emulator.args.extra = params.extra_currencies;
+    apply_get_method_params(&mut emulator, params.base, config)?;
+    Ok(emulator)
+}
+
+/// Applies params which are not related to the contract state.
+///
+/// Must be called after the state is set since gas params depend on the address.
+fn apply_get_method_params(
+    emulator: &mut TvmEmulator,
+    params: AccountGetMethodParams,
+    config: &str,
+) -> Result<()> {
+    let config = Boc::decode_base64(config)
+        .context("Failed to deserialize config cell")
+        .with_kind(ErrorKind::ConfigError)?;
+    let config = tvm_emulator::ParsedConfig::try_from_root(config)
+        .context("Failed to deserialize config")?;
+
+    let prev_blocks = parse_prev_blocks_info(params.prev_blocks_info.as_ref())?;
+
+    emulator.args.libraries = params.libs.map(emulator_libs_to_simple).transpose()?;
+    emulator.args.now = Some(params.unixtime);
+    emulator.args.rand_seed = Some(params.rand_seed);
+    emulator.args.debug_enabled = params.debug_enabled;
+    emulator.args.return_c7 = params.return_c7;
+    emulator.args.global_version = params.global_version;
+    emulator.args.trace = params.trace;
+    emulator.args.log_limits = params.log_limits.unwrap_or_default();
+    emulator.args.config = Some(config);
+    emulator.args.prev_blocks_info = prev_blocks;
+
+    if params.gas_limit > 0 {
+        emulator.set_gas_limit(params.gas_limit);
+    }
+
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn run_get_method_on_account(
+    params: &str,
+    account: &str,
+    stack: &str,
+    config: &str,
+) -> js_sys::JsString {
+    let mut resolved_method_id = None;
+    (|| {
+        let params: AccountGetMethodParams = serde_json::from_str(params)
+            .context("Can't decode params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let method_id = params.resolve_method_id()?;
+        resolved_method_id = Some(method_id);
+
+        let account = parse_shard_account(account)?;
+
+        let stack = parse_stack(stack)?;
+
+        let stack_format = params.stack_format;
+        let mut emulator =
+            TvmEmulator::new(Cell::empty_cell(), Cell::empty_cell(), params.verbosity);
+        emulator.set_account(&account)?;
+        apply_get_method_params(&mut emulator, params, config)?;
+
+        run_get_method_impl(&emulator, method_id, stack, stack_format)
+    })()
+    .unwrap_or_else(|e| make_get_method_error(e, resolved_method_id))
+}
+
+/// Creates a stateful TVM emulator from get-method params.
+///
+/// Method fields of `params` are ignored.
+#[wasm_bindgen]
+pub fn create_tvm_emulator(params: &str, config: &str) -> Result<*mut TvmEmulator, JsError> {
+    (|| {
+        let params: RunGetMethodParams = serde_json::from_str(params)
+            .context("Can't decode params")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let emulator = make_get_method_emulator(params, config)?;
+        Ok::<_, anyhow::Error>(Box::into_raw(Box::new(emulator)))
+    })()
+    .map_err(|e| JsError::new(&e.to_string()))
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn destroy_tvm_emulator(tvm_emulator: *mut TvmEmulator) -> Result<(), JsError> {
+    if tvm_emulator.is_null() {
+        return Err(JsError::new("tvm_emulator is null"));
+    }
+
+    _ = unsafe { Box::from_raw(tvm_emulator) };
+    Ok(())
+}
+
+/// Overrides verbosity-based VM log limits with JSON `LogLimits`.
+///
+/// Passing `undefined` restores the defaults.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_set_log_limits(
+    tvm_emulator: *mut TvmEmulator,
+    limits: Option<String>,
+) -> Result<(), JsError> {
+    if tvm_emulator.is_null() {
+        return Err(JsError::new("tvm_emulator is null"));
+    }
+
+    let limits = parse_log_limits(limits.as_deref()).map_err(|e| JsError::new(&e.to_string()))?;
+    let emulator = unsafe { &mut *tvm_emulator };
+    emulator.args.log_limits = limits;
+    Ok(())
+}
+
+/// Streams VM log rows to `callback(row)` instead of buffering them.
+///
+/// Passing `undefined` returns to the buffered log.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_set_log_callback(
+    tvm_emulator: *mut TvmEmulator,
+    callback: Option<js_sys::Function>,
+) -> Result<(), JsError> {
+    if tvm_emulator.is_null() {
+        return Err(JsError::new("tvm_emulator is null"));
+    }
+
+    let emulator = unsafe { &mut *tvm_emulator };
+    emulator.log_sink = callback.map(make_js_log_sink);
+    Ok(())
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_run_get_method(tvm_emulator: *mut TvmEmulator, call: &str) -> js_sys::JsString {
+    (|| {
+        anyhow::ensure!(!tvm_emulator.is_null(), "tvm_emulator pointer is null");
+        let emulator = unsafe { &mut *tvm_emulator };
+
+        let call: GetMethodCall = serde_json::from_str(call)
+            .context("Can't decode get-method call")
+            .with_kind(ErrorKind::InvalidInput)?;
+        let output = call.run(emulator, StackFormat::Boc);
+
+        let res = serde_json::to_string(&OkResponse { output }).unwrap();
+        Ok(JsValue::from(res).unchecked_into())
+    })()
+    .unwrap_or_else(|e| make_get_method_error(e, None))
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_send_external_message(
+    tvm_emulator: *mut TvmEmulator,
+    body: &str,
+) -> js_sys::JsString {
+    (|| {
+        anyhow::ensure!(!tvm_emulator.is_null(), "tvm_emulator pointer is null");
+        let emulator = unsafe { &mut *tvm_emulator };
+
+        let body = Boc::decode_base64(body)
+            .context("Failed to parse message body boc")
+            .with_kind(ErrorKind::InvalidInput)?;
 
         let subscriber = emulator.make_logger();
         let vm_log = subscriber.state().clone();
         let _tracing = tracing::subscriber::set_default(subscriber);
 
-        emulator.args.libraries = params.libs.map(emulator_libs_to_simple).transpose()?;
-        emulator.args.address = Some(params.address);
-        emulator.args.now = Some(params.unixtime);
-        emulator.args.balance = params.balance;
-        emulator.args.extra = params.extra_currencies;
-        emulator.args.rand_seed = Some(params.rand_seed);
-        emulator.args.debug_enabled = params.debug_enabled;
-        emulator.args.config = Some(config);
-        emulator.args.prev_blocks_info = prev_blocks;
-
-        if params.gas_limit > 0 {
-            emulator.set_gas_limit(params.gas_limit);
-        }
+        let res = emulator.send_external_message(body)?;
+        let output = TvmEmulatorSendMessageResponse::new(res, vm_log)?;
 
-        let res = emulator.run_get_method(params.method_id, stack);
-        let res = serde_json::to_string(&OkResponse {
-            output: RunGetMethodResponse {
-                success: JsonBool,
-                stack: res.stack,
-                gas_used: res.gas_used,
-                debug_log: res.debug_log,
-                vm_exit_code: res.exit_code,
-                vm_log,
-                missing_library: res.missing_library,
-            },
+        let res = serde_json::to_string(&OkResponse { output }).unwrap();
+        Ok(JsValue::from(res).unchecked_into())
+    })()
+    .unwrap_or_else(|e| {
+        let value = serde_json::to_string(&ErrResponse {
+            message: e.to_string(),
+            error_kind: ErrorKind::of(&e),
         })
         .unwrap();
-        Ok::<_, anyhow::Error>(JsValue::from(res).unchecked_into())
+
+        JsValue::from(value).unchecked_into()
+    })
+}
+
+/// Runs `recv_internal` with a message specified either as
+/// JSON `InternalMessageParams` or as a base64 encoded message BOC.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_send_internal_message(
+    tvm_emulator: *mut TvmEmulator,
+    message: &str,
+) -> js_sys::JsString {
+    (|| {
+        anyhow::ensure!(!tvm_emulator.is_null(), "tvm_emulator pointer is null");
+        let emulator = unsafe { &mut *tvm_emulator };
+
+        let message = message.trim();
+        let message = if message.starts_with('{') {
+            serde_json::from_str::<InternalMessageParams>(message)
+                .context("Failed to parse message params")
+                .with_kind(ErrorKind::InvalidInput)?
+                .build(emulator.args.address())
+                .context("Failed to build message")?
+        } else {
+            Boc::decode_base64(message)
+                .context("Failed to parse message boc")
+                .with_kind(ErrorKind::InvalidInput)?
+        };
+
+        let subscriber = emulator.make_logger();
+        let vm_log = subscriber.state().clone();
+        let _tracing = tracing::subscriber::set_default(subscriber);
+
+        let res = emulator.send_internal_message_full(message)?;
+        let output = TvmEmulatorSendMessageResponse::new(res, vm_log)?;
+
+        let res = serde_json::to_string(&OkResponse { output }).unwrap();
+        Ok(JsValue::from(res).unchecked_into())
     })()
     .unwrap_or_else(|e| {
         let value = serde_json::to_string(&ErrResponse {
             message: e.to_string(),
+            error_kind: ErrorKind::of(&e),
         })
         .unwrap();
 
@@ -323,6 +655,157 @@ pub fn run_get_method(params: &str, stack: &str, config: &str) -> js_sys::JsStri
     })
 }
 
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_snapshot(
+    tvm_emulator: *mut TvmEmulator,
+) -> Result<*mut TvmEmulatorSnapshot, JsError> {
+    if tvm_emulator.is_null() {
+        return Err(JsError::new("tvm_emulator is null"));
+    }
+
+    let emulator = unsafe { &*tvm_emulator };
+    Ok(Box::into_raw(Box::new(emulator.snapshot())))
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn tvm_emulator_restore(
+    tvm_emulator: *mut TvmEmulator,
+    snapshot: *mut TvmEmulatorSnapshot,
+) -> Result<(), JsError> {
+    if tvm_emulator.is_null() {
+        return Err(JsError::new("tvm_emulator is null"));
+    }
+    if snapshot.is_null() {
+        return Err(JsError::new("snapshot is null"));
+    }
+
+    let emulator = unsafe { &mut *tvm_emulator };
+    emulator.restore(unsafe { &*snapshot });
+    Ok(())
+}
+
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[wasm_bindgen]
+pub fn destroy_tvm_emulator_snapshot(snapshot: *mut TvmEmulatorSnapshot) -> Result<(), JsError> {
+    if snapshot.is_null() {
+        return Err(JsError::new("snapshot is null"));
+    }
+
+    _ = unsafe { Box::from_raw(snapshot) };
+    Ok(())
+}
+
+fn parse_shard_account(boc: &str) -> Result<ShardAccount> {
+    Boc::decode_base64(boc)
+        .context("Failed to deserialize shard account")
+        .with_kind(ErrorKind::InvalidInput)?
+        .parse::<ShardAccount>()
+        .context("Failed to unpack shard account")
+        .with_kind(ErrorKind::InvalidInput)
+}
+
+fn parse_emulation_input(
+    message: Option<String>,
+    params: &EmulatorParams,
+) -> Result<EmulationInput> {
+    if let Some(msg) = message {
+        anyhow::ensure!(
+            !params.is_tick_tock,
+            "Tick-tock transactions cannot have an inbound message"
+        );
+
+        let msg = Boc::decode_base64(msg).context("Failed to deserialize message")?;
+        Ok(EmulationInput::Ordinary(msg))
+    } else {
+        anyhow::ensure!(
+            params.is_tick_tock,
+            "Inbound message is required for ordinary transactions"
+        );
+        Ok(EmulationInput::TickTock(if params.is_tock {
+            TickTock::Tock
+        } else {
+            TickTock::Tick
+        }))
+    }
+}
+
+fn parse_log_limits(limits: Option<&str>) -> Result<LogLimits> {
+    match limits {
+        Some(limits) => serde_json::from_str(limits)
+            .context("Can't decode log limits")
+            .with_kind(ErrorKind::InvalidInput),
+        None => Ok(LogLimits::default()),
+    }
+}
+
+fn make_js_log_sink(callback: js_sys::Function) -> LogSink {
+    struct JsCallback(js_sys::Function);
+
+    impl JsCallback {
+        fn call(&self, row: &str) {
+            // Errors thrown by the callback are ignored.
+            _ = self.0.call1(&JsValue::NULL, &JsValue::from_str(row));
+        }
+    }
+
+    // SAFETY: Wasm module is single-threaded.
+    unsafe impl Send for JsCallback {}
+    unsafe impl Sync for JsCallback {}
+
+    let callback = JsCallback(callback);
+    Arc::new(move |row: &str| callback.call(row))
+}
+
+fn run_get_method_impl(
+    emulator: &TvmEmulator,
+    method_id: i32,
+    stack: Stack,
+    stack_format: StackFormat,
+) -> Result<js_sys::JsString> {
+    let outcome = emulator.run_get_method_logged(method_id, stack)?;
+    let output = RunGetMethodResponse::new(outcome, stack_format)?;
+    let res = serde_json::to_string(&OkResponse { output }).unwrap();
+    Ok(JsValue::from(res).unchecked_into())
+}
+
+fn make_get_method_error(e: anyhow::Error, method_id: Option<i32>) -> js_sys::JsString {
+    let value = serde_json::to_string(&RunGetMethodErrResponse {
+        message: e.to_string(),
+        error_kind: ErrorKind::of(&e),
+        method_id,
+    })
+    .unwrap();
+
+    JsValue::from(value).unchecked_into()
+}
+
+fn make_emulation_params(
+    params: &EmulatorParams,
+    libraries: Dict<HashBytes, LibDescr>,
+    prev_blocks_info: Option<tycho_vm::SafeRc<tycho_vm::Tuple>>,
+) -> EmulationParams {
+    EmulationParams {
+        block_unixtime: params.unixtime,
+        block_lt: params.lt,
+        libraries,
+        prev_blocks_info,
+        ignore_chksig: params.ignore_chksig,
+        debug_enabled: params.debug_enabled,
+        disable_delete_frozen_accounts: params.disable_delete_frozen_accounts.unwrap_or(true),
+        charge_action_fees_on_fail: params.charge_action_fees_on_fail.unwrap_or(true),
+        full_body_in_bounced: params.full_body_in_bounced.unwrap_or(false),
+        strict_extra_currency: params.strict_extra_currency.unwrap_or(true),
+        authority_marks_enabled: params.authority_marks_enabled.unwrap_or(false),
+        data_cells_diff: params.data_cells_diff,
+        return_c7: params.return_c7,
+        trace: params.trace,
+        log_limits: params.log_limits,
+        vm_log_mask: params.vm_log_mask(),
+    }
+}
+
 fn emulator_libs_to_shard(libs_root: Cell) -> Result<Dict<HashBytes, LibDescr>> {
     thread_local! {
         static COMMON_PUBLISHER: Dict<HashBytes, ()> = {
@@ -337,7 +820,9 @@ fn emulator_libs_to_shard(libs_root: Cell) -> Result<Dict<HashBytes, LibDescr>>
 
         let mut items = Vec::new();
         for item in libs.iter() {
-            let (hash, lib) = item.context("Invalid libraries dict")?;
+            let (hash, lib) = item
+                .context("Invalid libraries dict")
+                .with_kind(ErrorKind::InvalidInput)?;
             items.push((hash, LibDescr {
                 lib,
                 publishers: publishers.clone(),
@@ -353,28 +838,11 @@ fn emulator_libs_to_simple(libs_root: Cell) -> Result<Dict<HashBytes, SimpleLib>
 
     let mut items = Vec::new();
     for item in libs.iter() {
-        let (hash, root) = item.context("Invalid libraries dict")?;
+        let (hash, root) = item
+            .context("Invalid libraries dict")
+            .with_kind(ErrorKind::InvalidInput)?;
         items.push((hash, SimpleLib { root, public: true }));
     }
 
     Dict::try_from_sorted_slice(&items).context("Failed to repack libraries dict")
 }
-
-fn parse_prev_blocks_info(
-    prev_blocks_info: Option<&Cell>,
-) -> Result<Option<tycho_vm::SafeRc<tycho_vm::Tuple>>> {
-    Ok(if let Some(prev_blocks) = prev_blocks_info {
-        let info_value = Stack::load_stack_value_from_cell(prev_blocks.as_ref())
-            .context("Failed to deserialize previous blocks tuple")?;
-
-        if info_value.is_null() {
-            None
-        } else if let Ok(tuple) = info_value.into_tuple() {
-            Some(tuple)
-        } else {
-            anyhow::bail!("Failed to set previous blocks tuple: not a tuple");
-        }
-    } else {
-        None
-    })
-}
@@ -2,11 +2,20 @@
 
 #include <stdint.h>
 #include <stdbool.h>
+#include <stddef.h>
 
 #ifdef __cplusplus
 extern "C" {
 #endif
 
+/**
+ * @brief VM log callback
+ * @param user_data Pointer passed when the callback was set
+ * @param line VM log row (not null-terminated, valid only during the call)
+ * @param len Length of the row in bytes
+ */
+typedef void (*emulator_log_callback)(void *user_data, const char *line, size_t len);
+
 /**
  * @brief Creates TransactionEmulator object
  * @param config_params_boc Base64 encoded BoC serialized Config dictionary (Hashmap 32 ^Cell)
@@ -39,6 +48,19 @@ bool transaction_emulator_set_unixtime(void *transaction_emulator, uint32_t unix
  */
 bool transaction_emulator_set_lt(void *transaction_emulator, uint64_t lt);
 
+/**
+ * @brief Enable automatic lt and unixtime chaining between emulations
+ *
+ * Applies to `transaction_emulator_emulate_transaction(_ext)`, `transaction_emulator_emulate_tick_tock_transaction`,
+ * `transaction_emulator_emulate_batch` and `local_shard_execute`. All jobs of a batch use the same params,
+ * lt and unixtime are moved past all committed transactions of the batch or of the local shard execution.
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param enabled If true, lt is moved past end lt of each committed transaction
+ * @param unixtime_step If non-zero, unixtime is set to transaction unixtime plus this step after each committed transaction
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_auto_advance(void *transaction_emulator, bool enabled, uint32_t unixtime_step);
+
 /**
  * @brief Set rand seed for emulation
  * @param transaction_emulator Pointer to TransactionEmulator object
@@ -87,6 +109,86 @@ bool transaction_emulator_set_libs(void *transaction_emulator, const char* libs_
  */
 bool transaction_emulator_set_debug_enabled(void *transaction_emulator, bool debug_enabled);
 
+/**
+ * @brief Enable computing cell-level diff of the account data in `state_diff.data_cells`
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param data_cells_diff Whether to list data cells added and removed by the transaction
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_data_cells_diff(void *transaction_emulator, bool data_cells_diff);
+
+/**
+ * @brief Enable or disable returning c7 of the compute phase
+ *
+ * When enabled, emulation results contain an additional field:
+ * "c7": { "boc": "Base64 encoded BoC serialized c7 (VmStackValue)", "json": { "type": "tuple", "value": [...] } }
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param return_c7 Whether c7 should be returned
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_return_c7(void *transaction_emulator, bool return_c7);
+
+/**
+ * @brief Set structured trace mode of the compute phase
+ *
+ * When enabled, emulation results contain an additional field with one object per executed instruction:
+ * "trace": [{ "opcode": "PUSHINT 1", "code_hash": "...", "offset": 8, "gas_remaining": 999982, "gas_consumed": 18 }]
+ * Gas consumed by the last instruction is null. Text VM log is not affected.
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param trace_level 0 - disabled, 1 - enabled, 2 - enabled with a stack snapshot ("stack" field) before each instruction
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_trace(void *transaction_emulator, int trace_level);
+
+/**
+ * @brief Set VM log verbosity without recreating the emulator
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param vm_log_verbosity Verbosity level of VM log (same as in `transaction_emulator_create`)
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_vm_log_verbosity(void *transaction_emulator, int vm_log_verbosity);
+
+/**
+ * @brief Set VM log mask without recreating the emulator
+ *
+ * Mask bits: 1 - messages, 2 - stack, 4 - code cell hash and offset, 8 - gas remaining,
+ * 16 - verbose stack, 32 - c5.
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param vm_log_mask Bit set of VM log flags
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_vm_log_mask(void *transaction_emulator, uint32_t vm_log_mask);
+
+/**
+ * @brief Override verbosity-based VM log limits
+ *
+ * Limits are specified as a Json object (all fields are optional):
+ * {
+ *   "max_rows": 1000,
+ *   "max_bytes": 65536, // total size of the kept rows including newlines
+ *   "retention": "head_tail" // "head", "tail" (default) or "head_tail" with an elided middle
+ * }
+ * Dropped rows are reported in results as "vm_log_dropped": { "rows": 10, "bytes": 480 }.
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param limits_json Json object with limits, NULL to restore the defaults
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_log_limits(void *transaction_emulator, const char *limits_json);
+
+/**
+ * @brief Stream VM log rows to the callback as they are produced
+ *
+ * Rows are not buffered or truncated in this mode, so "vm_log" field of emulation results is empty.
+ * The callback is called from a worker thread in `transaction_emulator_emulate_batch`. While the callback
+ * is set, batch jobs are emulated one by one in their order, so rows of different jobs never interleave.
+ * The callback may call other functions of the emulator library.
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param callback Log callback, NULL to return to the buffered log
+ * @param user_data Pointer passed to each callback call
+ * @return true in case of success, false in case of error
+ */
+bool transaction_emulator_set_log_callback(void *transaction_emulator, emulator_log_callback callback, void *user_data);
+
 /**
  * @brief Set tuple of previous blocks (13th element of c7)
  * @param transaction_emulator Pointer to TransactionEmulator object
@@ -104,13 +206,18 @@ bool transaction_emulator_set_prev_blocks_info(void *transaction_emulator, const
  * {
  *   "success": false,
  *   "error": "Error description",
+ *   "error_kind": "invalid_input", // "config_error", "executor_fatal", "not_accepted" or "internal"
  *   "external_not_accepted": false,
- *   // and optional fields "vm_exit_code", "vm_log", "elapsed_time" in case external message was not accepted.
+ *   // and optional fields "vm_exit_code", "vm_exit_code_name", "vm_exit_code_description", "vm_log", "elapsed_time" in case external message was not accepted.
  * }
  * Or success:
  * {
  *   "success": true,
  *   "transaction": "Base64 encoded Transaction boc",
+ *   "description": { "kind": "ordinary", "compute_phase": { "type": "executed", ... }, ... },
+ *   "out_messages": [{ "boc": "...", "hash": "...", "type": "int", "src": "0:...", "dst": "0:...", "value": "1000", "value_extra": {}, "bounce": true, "bounced": false, "fwd_fee": "1000", "created_lt": "1001", "created_at": 1700000000 }],
+ *   "fees": { "storage_fees_collected": "0", "storage_fees_due": "0", "gas_fees": "0", "action_fees": "0", "total_fwd_fees": "0", "bounce_fees": "0", "total_fees": "0", "out_msgs": [{ "hash": "...", "fwd_fee": "0", "action_fee": "0", "remaining_fwd_fee": "0" }] },
+ *   "state_diff": { "status_before": "uninit", "status_after": "active", "balance_delta": "-1000", "code_changed": true, "data_changed": true, ... },
  *   "shard_account": "Base64 encoded new ShardAccount boc",
  *   "vm_log": "execute DUP...",
  *   "actions": "Base64 encoded compute phase actions boc (OutList n)",
@@ -119,6 +226,21 @@ bool transaction_emulator_set_prev_blocks_info(void *transaction_emulator, const
  */
 const char *transaction_emulator_emulate_transaction(void *transaction_emulator, const char *shard_account_boc, const char *message_boc);
 
+/**
+ * @brief Emulate transaction with per-call overrides of the emulator settings
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param shard_account_boc Base64 encoded BoC serialized ShardAccount
+ * @param message_boc Base64 encoded BoC serialized inbound Message (internal or external)
+ * @param params_json Json object with overrides (all fields are optional), NULL to use the emulator settings:
+ * {
+ *   "log_limits": { "max_rows": 1000, "max_bytes": 65536, "retention": "head_tail" }, // see `transaction_emulator_set_log_limits`
+ *   "vm_log_verbosity": 1,
+ *   "vm_log_mask": 3 // see `transaction_emulator_set_vm_log_mask`, takes precedence over "vm_log_verbosity"
+ * }
+ * @return Json object in the same format as `transaction_emulator_emulate_transaction`
+ */
+const char *transaction_emulator_emulate_transaction_ext(void *transaction_emulator, const char *shard_account_boc, const char *message_boc, const char *params_json);
+
 /**
  * @brief Emulate tick tock transaction
  * @param transaction_emulator Pointer to TransactionEmulator object
@@ -128,12 +250,17 @@ const char *transaction_emulator_emulate_transaction(void *transaction_emulator,
  * {
  *   "success": false,
  *   "error": "Error description",
+ *   "error_kind": "invalid_input",
  *   "external_not_accepted": false
  * }
  * Or success:
  * {
  *   "success": true,
  *   "transaction": "Base64 encoded Transaction boc",
+ *   "description": { "kind": "tick", "compute_phase": { "type": "executed", ... }, ... },
+ *   "out_messages": [{ "boc": "...", "hash": "...", "type": "int", "src": "0:...", "dst": "0:...", "value": "1000", "value_extra": {}, "bounce": true, "bounced": false, "fwd_fee": "1000", "created_lt": "1001", "created_at": 1700000000 }],
+ *   "fees": { "storage_fees_collected": "0", "storage_fees_due": "0", "gas_fees": "0", "action_fees": "0", "total_fwd_fees": "0", "bounce_fees": "0", "total_fees": "0", "out_msgs": [{ "hash": "...", "fwd_fee": "0", "action_fee": "0", "remaining_fwd_fee": "0" }] },
+ *   "state_diff": { "status_before": "uninit", "status_after": "active", "balance_delta": "-1000", "code_changed": true, "data_changed": true, ... },
  *   "shard_account": "Base64 encoded new ShardAccount boc",
  *   "vm_log": "execute DUP...",
  *   "actions": "Base64 encoded compute phase actions boc (OutList n)",
@@ -142,12 +269,97 @@ const char *transaction_emulator_emulate_transaction(void *transaction_emulator,
  */
 const char *transaction_emulator_emulate_tick_tock_transaction(void *transaction_emulator, const char *shard_account_boc, bool is_tock);
 
+/**
+ * @brief Emulate a batch of independent transactions with the same emulator settings
+ * @param transaction_emulator Pointer to TransactionEmulator object
+ * @param jobs_json Json array of jobs:
+ * [
+ *   {
+ *     "shard_account": "Base64 encoded BoC serialized ShardAccount",
+ *     "message": "Base64 encoded BoC serialized inbound Message (omit for tick tock)",
+ *     "is_tock": false
+ *   }
+ * ]
+ * @param threads Number of worker threads. 0 or 1 - emulate sequentially.
+ * @return Json array of results in the same order as jobs, each in the same format
+ * as in `transaction_emulator_emulate_transaction`. Or json object with error in case
+ * of invalid input:
+ * {
+ *   "success": false,
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input",
+ *   "external_not_accepted": false
+ * }
+ */
+const char *transaction_emulator_emulate_batch(void *transaction_emulator, const char *jobs_json, uint32_t threads);
+
 /**
  * @brief Destroy TransactionEmulator object
  * @param transaction_emulator Pointer to TransactionEmulator object
  */
 void transaction_emulator_destroy(void *transaction_emulator);
 
+/**
+ * @brief Creates LocalShard object which routes internal messages between its accounts
+ * @param max_transactions Max number of transactions produced by a single inbound message. 0 - default limit.
+ * @return Pointer to LocalShard or nullptr in case of error
+ */
+void *local_shard_create(uint32_t max_transactions);
+
+/**
+ * @brief Set account state in the local shard
+ * @param local_shard Pointer to LocalShard object
+ * @param address Address of the account
+ * @param shard_account_boc Base64 encoded BoC serialized ShardAccount
+ * @return true in case of success, false in case of error
+ */
+bool local_shard_set_account(void *local_shard, const char *address, const char *shard_account_boc);
+
+/**
+ * @brief Get account state from the local shard
+ * @param local_shard Pointer to LocalShard object
+ * @param address Address of the account
+ * @return Base64 encoded BoC serialized ShardAccount or nullptr if account is not in the shard
+ */
+const char *local_shard_get_account(void *local_shard, const char *address);
+
+/**
+ * @brief Execute inbound message and deliver all produced internal messages in lt order
+ * @param local_shard Pointer to LocalShard object
+ * @param transaction_emulator Pointer to TransactionEmulator object which params are used for all transactions
+ * @param message_boc Base64 encoded BoC serialized inbound Message (internal or external)
+ * @return Json object with error:
+ * {
+ *   "success": false,
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input",
+ *   "external_not_accepted": false
+ * }
+ * Or success:
+ * {
+ *   "success": true,
+ *   "transactions": [
+ *     {
+ *       "address": "0:...",
+ *       "parent": null,
+ *       "children": [1, 2],
+ *       "transaction": "Base64 encoded Transaction boc",
+ *       "shard_account": "Base64 encoded new ShardAccount boc",
+ *       "vm_log": "execute DUP...",
+ *       "actions": "Base64 encoded compute phase actions boc (OutList n)"
+ *     },
+ *     ...
+ *   ]
+ * }
+ */
+const char *local_shard_execute(void *local_shard, void *transaction_emulator, const char *message_boc);
+
+/**
+ * @brief Destroy LocalShard object
+ * @param local_shard Pointer to LocalShard object
+ */
+void local_shard_destroy(void *local_shard);
+
 /**
  * @brief Set global verbosity level of the library
  * @param verbosity_level New verbosity level (0 - never, 1 - error, 2 - warning, 3 - info, 4 - debug)
@@ -185,7 +397,9 @@ bool tvm_emulator_set_c7(void *tvm_emulator, const char *address, uint32_t unixt
 /**
  * @brief Set extra currencies balance
  * @param tvm_emulator Pointer to TVM emulator
- * @param extra_currencies String with extra currencies balance in format "currency_id1=balance1 currency_id2=balance2 ..."
+ * @param extra_currencies String with extra currencies balance in format "currency_id1=balance1 currency_id2=balance2 ...".
+ * A Json object { "currency_id1": "balance1", ... } is also accepted. Null, empty string or "null" to clear
+ * extra currencies.
  * @return true in case of success, false in case of error
  */
 bool tvm_emulator_set_extra_currencies(void *tvm_emulator, const char *extra_currencies);
@@ -208,6 +422,8 @@ bool tvm_emulator_set_prev_blocks_info(void *tvm_emulator, const char* info_boc)
 
 /**
  * @brief Set TVM gas limit
+ *
+ * Max gas and gas price are taken from the config if it was set before this call.
  * @param tvm_emulator Pointer to TVM emulator
  * @param gas_limit Gas limit
  * @return true in case of success, false in case of error
@@ -222,28 +438,166 @@ bool tvm_emulator_set_gas_limit(void *tvm_emulator, int64_t gas_limit);
  */
 bool tvm_emulator_set_debug_enabled(void *tvm_emulator, bool debug_enabled);
 
+/**
+ * @brief Enable or disable returning c7 used for the run
+ *
+ * When enabled, get method and send message results contain an additional field:
+ * "c7": { "boc": "Base64 encoded BoC serialized c7 (VmStackValue)", "json": { "type": "tuple", "value": [...] } }
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param return_c7 Whether c7 should be returned
+ * @return true in case of success, false in case of error
+ */
+bool tvm_emulator_set_return_c7(void *tvm_emulator, bool return_c7);
+
+/**
+ * @brief Set structured trace mode
+ *
+ * When enabled, get method and send message results contain an additional field with one object per executed instruction:
+ * "trace": [{ "opcode": "PUSHINT 1", "code_hash": "...", "offset": 8, "gas_remaining": 999982, "gas_consumed": 18 }]
+ * Gas consumed by the last instruction is null. Text VM log is not affected.
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param trace_level 0 - disabled, 1 - enabled, 2 - enabled with a stack snapshot ("stack" field) before each instruction
+ * @return true in case of success, false in case of error
+ */
+bool tvm_emulator_set_trace(void *tvm_emulator, int trace_level);
+
+/**
+ * @brief Override verbosity-based VM log limits
+ *
+ * Limits are specified as a Json object (all fields are optional):
+ * {
+ *   "max_rows": 1000,
+ *   "max_bytes": 65536, // total size of the kept rows including newlines
+ *   "retention": "head_tail" // "head", "tail" (default) or "head_tail" with an elided middle
+ * }
+ * Dropped rows are reported in results as "vm_log_dropped": { "rows": 10, "bytes": 480 }.
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param limits_json Json object with limits, NULL to restore the defaults
+ * @return true in case of success, false in case of error
+ */
+bool tvm_emulator_set_log_limits(void *tvm_emulator, const char *limits_json);
+
+/**
+ * @brief Stream VM log rows to the callback as they are produced
+ *
+ * Rows are not buffered or truncated in this mode, so "vm_log" field of results is empty.
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param callback Log callback, NULL to return to the buffered log
+ * @param user_data Pointer passed to each callback call
+ * @return true in case of success, false in case of error
+ */
+bool tvm_emulator_set_log_callback(void *tvm_emulator, emulator_log_callback callback, void *user_data);
+
+/**
+ * @brief Override global version used for c7 layout and TVM instruction set
+ *
+ * The version used for the run is reported as "vm_version" in the results.
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param global_version Global version, 0 to use the one from config
+ * @return true in case of success, false in case of error
+ */
+bool tvm_emulator_set_global_version(void *tvm_emulator, uint32_t global_version);
+
 /**
  * @brief Run get method
  * @param tvm_emulator Pointer to TVM emulator
  * @param method_id Integer method id
- * @param stack_boc Base64 encoded BoC serialized stack (VmStack)
+ * @param stack_boc Base64 encoded BoC serialized stack (VmStack) or JSON stack (see `tvm_emulator_run_get_method_json`)
  * @return Json object with error:
  * {
  *   "success": false,
- *   "error": "Error description"
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input"
  * }
  * Or success:
  * {
  *   "success": true
+ *   "method_id": 85143,
  *   "vm_log": "...",
  *   "vm_exit_code": 0,
+ *   "vm_exit_code_name": "ok",
+ *   "vm_exit_code_description": "Successful execution",
  *   "stack": "Base64 encoded BoC serialized stack (VmStack)",
  *   "missing_library": null,
- *   "gas_used": 1212
+ *   "gas_used": 1212,
+ *   "vm_version": 11
  * }
  */
 const char *tvm_emulator_run_get_method(void *tvm_emulator, int method_id, const char *stack_boc);
 
+/**
+ * @brief Run get method by its name or numeric id
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param method Method name (id is computed as `crc16(name) | 0x10000`) or integer method id as string
+ * @param stack_boc Base64 encoded BoC serialized stack (VmStack)
+ * @return Json object with error:
+ * {
+ *   "success": false,
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input",
+ *   "method_id": 85143 // resolved method id
+ * }
+ * Or success in the same format as in `tvm_emulator_run_get_method`.
+ */
+const char *tvm_emulator_run_get_method_ext(void *tvm_emulator, const char *method, const char *stack_boc);
+
+/**
+ * @brief Run get method with a typed JSON stack for both input and output
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param method Method name or integer method id as string
+ * @param stack Json array of stack entries (from bottom to top) or Base64 encoded BoC serialized stack (VmStack):
+ * [
+ *   { "type": "null" },
+ *   { "type": "nan" },
+ *   { "type": "int", "value": "-123" },
+ *   { "type": "cell", "value": "Base64 encoded BoC" },
+ *   { "type": "slice", "value": "Base64 encoded BoC", "window": { "bits_offset": 0, "refs_offset": 0, "bits": 267, "refs": 0 } },
+ *   { "type": "builder", "value": "Base64 encoded BoC with builder contents" },
+ *   { "type": "tuple", "value": [ ... ] }
+ * ]
+ * Slice `window` is optional, the whole cell is used by default.
+ * @return Json object in the same format as in `tvm_emulator_run_get_method_ext`,
+ * but with `stack` as a Json array of stack entries. Continuations are not supported.
+ */
+const char *tvm_emulator_run_get_method_json(void *tvm_emulator, const char *method, const char *stack);
+
+/**
+ * @brief Run get method against a shard account
+ *
+ * Code, data, address, balance, extra currencies and due payment are taken from the account,
+ * other c7 params are taken from the emulator. The emulator state is not changed.
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param shard_account_boc Base64 encoded BoC serialized ShardAccount. Account must be active.
+ * @param method Method name or integer method id as string
+ * @param stack_boc Base64 encoded BoC serialized stack (VmStack) or JSON stack
+ * @return Json object in the same format as in `tvm_emulator_run_get_method_ext`
+ */
+const char *tvm_emulator_run_get_method_on_account(void *tvm_emulator, const char *shard_account_boc, const char *method, const char *stack_boc);
+
+/**
+ * @brief Run multiple get methods against the same emulator state
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param calls_json Json array of calls:
+ * [
+ *   {
+ *     "method": "get_balance", // method name or integer method id
+ *     "stack": "...", // (optional) Base64 encoded BoC serialized stack (VmStack) or Json array of stack entries
+ *     "gas_limit": "1000000", // (optional) overrides emulator gas limit for this call
+ *     "stack_format": "json" // (optional) "boc" (default) or "json"
+ *   }
+ * ]
+ * @return Json object with error in the same format as in `tvm_emulator_run_get_method`.
+ * Or Json array with one item per call. Each item is either a success object in the same
+ * format as in `tvm_emulator_run_get_method` or an error:
+ * {
+ *   "success": false,
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input",
+ *   "method_id": 85143
+ * }
+ */
+const char *tvm_emulator_run_get_methods(void *tvm_emulator, const char *calls_json);
+
 /**
  * @brief Optimized version of "run get method" with all passed parameters in a single call
  * @param len Length of params_boc buffer
@@ -262,13 +616,27 @@ const char *tvm_emulator_emulate_run_method(uint32_t len, const char *params_boc
  * @return Pointer to struct with two fields:
  * - response: Char* with first 4 bytes defining length, and the rest BoC serialized result
  *         Scheme: result$_ exit_code:(## 32) gas_used:(## 32) stack:^VmStack
- * - log: Char* with VM log string
+ * - log: Char* with VM log string followed by debug output.
+ *   VM log verbosity is 3, use "tvm_emulator_emulate_run_method_detailed_ext" to change it.
  */
 void *tvm_emulator_emulate_run_method_detailed(uint32_t len, const char *params_boc, int64_t gas_limit);
 
+/**
+ * @brief Same as "tvm_emulator_emulate_run_method_detailed" but with explicit VM log verbosity
+ * @param len Length of params_boc buffer
+ * @param params_boc BoC serialized parameters, scheme: request$_ code:^Cell data:^Cell stack:^VmStack params:^[c7:^VmStack libs:^Cell] method_id:(## 32)
+ * @param gas_limit Gas limit
+ * @param vm_log_verbosity Verbosity level of VM log. 0 - no VM log. 1 - executed instructions and VM messages.
+ * 2 - also code cell hash and offset of each instruction. 3 - also remaining gas. 4 - also stack before each instruction.
+ * 5 - verbose stack dumps.
+ * @return Pointer to struct in the same format as in "tvm_emulator_emulate_run_method_detailed"
+ */
+void *tvm_emulator_emulate_run_method_detailed_ext(uint32_t len, const char *params_boc, int64_t gas_limit, int vm_log_verbosity);
+
 /**
  * @brief Destroy detailed result of "tvm_emulator_emulate_run_method_detailed"
  * @param detailed_result Pointer to detailed result struct returned by "tvm_emulator_emulate_run_method_detailed"
+ * or "tvm_emulator_emulate_run_method_detailed_ext"
  *
  * Caller should not use string_destroy() for fields of this struct,
  * as they are already freed in this function.
@@ -282,7 +650,8 @@ void run_method_detailed_result_destroy(void *detailed_result);
  * @return Json object with error:
  * {
  *   "success": false,
- *   "error": "Error description"
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input"
  * }
  * Or success:
  * {
@@ -291,10 +660,13 @@ void run_method_detailed_result_destroy(void *detailed_result);
  *   "new_data": "Base64 boc decoded new data cell",
  *   "accepted": true,
  *   "vm_exit_code": 0,
+ *   "vm_exit_code_name": "ok",
+ *   "vm_exit_code_description": "Successful execution",
  *   "vm_log": "...",
  *   "missing_library": null,
  *   "gas_used": 1212,
- *   "actions": "Base64 boc decoded actions cell of type (OutList n)"
+ *   "actions": "Base64 boc decoded actions cell of type (OutList n)",
+ *   "vm_version": 11
  * }
  */
 const char *tvm_emulator_send_external_message(void *tvm_emulator, const char *message_body_boc);
@@ -304,10 +676,16 @@ const char *tvm_emulator_send_external_message(void *tvm_emulator, const char *m
  * @param tvm_emulator Pointer to TVM emulator
  * @param message_body_boc Base64 encoded BoC serialized message body cell.
  * @param amount Amount of nanograms attached with internal message.
+ *
+ * The message is sent from -1:0000000000000000000000000000000000000000000000000000000000000000
+ * with the bounce flag set. The full message cell is passed on the stack, and the `amount`
+ * is used as the c7 message balance, in the c7 `in_msg` params (global version 11+)
+ * and for the default gas limit (unless set with `tvm_emulator_set_gas_limit`).
  * @return Json object with error:
  * {
  *   "success": false,
- *   "error": "Error description"
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input"
  * }
  * Or success:
  * {
@@ -316,20 +694,67 @@ const char *tvm_emulator_send_external_message(void *tvm_emulator, const char *m
  *   "new_data": "Base64 boc decoded new data cell",
  *   "accepted": true,
  *   "vm_exit_code": 0,
+ *   "vm_exit_code_name": "ok",
+ *   "vm_exit_code_description": "Successful execution",
  *   "vm_log": "...",
  *   "missing_library": null,
  *   "gas_used": 1212,
- *   "actions": "Base64 boc decoded actions cell of type (OutList n)"
+ *   "actions": "Base64 boc decoded actions cell of type (OutList n)",
+ *   "vm_version": 11
  * }
  */
 const char *tvm_emulator_send_internal_message(void *tvm_emulator, const char *message_body_boc, uint64_t amount);
 
+/**
+ * @brief Send fully configurable internal message
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param message Base64 encoded BoC serialized internal Message, or Json object with message params
+ * (destination must be the emulated account, Json params always use it):
+ * {
+ *   "src": "-1:0000000000000000000000000000000000000000000000000000000000000000", // optional
+ *   "bounce": true,                                             // optional
+ *   "bounced": false,                                           // optional
+ *   "value": "1000000000",
+ *   "extra_currencies": { "100": "1000" },                      // optional
+ *   "fwd_fee": "0",                                             // optional
+ *   "created_lt": "0",                                          // optional
+ *   "created_at": 0,                                            // optional
+ *   "state_init": "Base64 encoded BoC serialized StateInit",    // optional
+ *   "body": "Base64 encoded BoC serialized message body cell"  // optional
+ * }
+ * @return Json object in the same format as in `tvm_emulator_send_internal_message`
+ */
+const char *tvm_emulator_send_internal_message_ext(void *tvm_emulator, const char *message);
+
 /**
  * @brief Destroy TVM emulator object
  * @param tvm_emulator Pointer to TVM emulator object
  */
 void tvm_emulator_destroy(void *tvm_emulator);
 
+/**
+ * @brief Capture code, data and all c7 params of TVM emulator
+ * @param tvm_emulator Pointer to TVM emulator
+ * @return Pointer to snapshot object or nullptr in case of error
+ */
+void *tvm_emulator_snapshot(void *tvm_emulator);
+
+/**
+ * @brief Restore TVM emulator state from snapshot. The same snapshot can be restored multiple times.
+ *
+ * The log callback is not part of the snapshot, the current one is kept.
+ * @param tvm_emulator Pointer to TVM emulator
+ * @param snapshot Pointer to snapshot object created with `tvm_emulator_snapshot`
+ * @return true in case of success, false in case of error
+ */
+bool tvm_emulator_restore(void *tvm_emulator, void *snapshot);
+
+/**
+ * @brief Destroy TVM emulator snapshot object
+ * @param snapshot Pointer to snapshot object
+ */
+void tvm_emulator_snapshot_destroy(void *snapshot);
+
 /**
  * @brief Destroy Config object
  * @param tvm_emulator Pointer to Config object
@@ -350,6 +775,33 @@ void string_destroy(const char *string);
  */
 const char* emulator_version();
 
+/**
+ * @brief Build ShardAccount with storage stats computed from its state
+ * @param params_json Json object with account params:
+ * {
+ *   "address": "0:...",
+ *   "balance": "1000000000",
+ *   "extra_currencies": { "100": "1000" },       // optional
+ *   "state": "active" | "uninit" | "frozen",     // optional, "active" by default
+ *   "code": "Base64 encoded BoC serialized code", // optional, not allowed for "uninit"
+ *   "data": "Base64 encoded BoC serialized data", // optional, not allowed for "uninit"
+ *   "last_paid": 1700000000,                      // optional
+ *   "last_trans_lt": "0"                          // optional
+ * }
+ * @return Json object with error:
+ * {
+ *   "success": false,
+ *   "error": "Error description",
+ *   "error_kind": "invalid_input"
+ * }
+ * Or success:
+ * {
+ *   "success": true,
+ *   "shard_account": "Base64 encoded ShardAccount boc"
+ * }
+ */
+const char *emulator_build_shard_account(const char *params_json);
+
 #ifdef __cplusplus
 }  // extern "C"
 #endif
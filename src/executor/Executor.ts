@@ -82,7 +82,6 @@ export class TychoExecutor implements IExecutor {
   private emulator?: {
     ptr: number;
     config: string;
-    verbosity: number;
   };
 
   constructor(
@@ -159,7 +158,7 @@ export class TychoExecutor implements IExecutor {
     };
 
     return this.runCommon(
-      this.getEmulatorPointer(args.config, verbosityToNum[args.verbosity]),
+      this.getEmulatorPointer(args.config),
       args.libs?.toBoc().toString("base64"),
       args.shardAccount,
       null,
@@ -173,7 +172,7 @@ export class TychoExecutor implements IExecutor {
     const params = runCommonArgsToInternalParams(args, this.executorParams);
 
     return this.runCommon(
-      this.getEmulatorPointer(args.config, verbosityToNum[args.verbosity]),
+      this.getEmulatorPointer(args.config),
       args.libs?.toBoc().toString("base64"),
       args.shardAccount,
       args.message.toBoc().toString("base64"),
@@ -228,28 +227,24 @@ export class TychoExecutor implements IExecutor {
     };
   }
 
-  private getEmulatorPointer(config: string, verbosity: number) {
-    if (
-      this.emulator === undefined ||
-      verbosity !== this.emulator.verbosity ||
-      config !== this.emulator.config
-    ) {
-      this.createEmulator(config, verbosity);
+  private getEmulatorPointer(config: string) {
+    if (this.emulator === undefined || config !== this.emulator.config) {
+      this.createEmulator(config);
     }
 
     return this.emulator!.ptr;
   }
 
-  private createEmulator(config: string, verbosity: number) {
+  private createEmulator(config: string) {
     if (this.emulator !== undefined) {
       this.module.destroy_emulator(this.emulator.ptr);
     }
 
-    const ptr = this.module.create_emulator(config, verbosity);
+    // VM log verbosity is passed with each call.
+    const ptr = this.module.create_emulator(config, 0);
     this.emulator = {
       ptr,
       config,
-      verbosity,
     };
   }
 }
@@ -264,6 +259,7 @@ function runCommonArgsToInternalParams(
     rand_seed: args.randomSeed?.toString("hex"),
     ignore_chksig: args.ignoreChksig,
     debug_enabled: args.debugEnabled,
+    vm_log_verbosity: verbosityToNum[args.verbosity],
     disable_delete_frozen_accounts: executorParams.disableDeleteFrozenAccounts,
     charge_action_fees_on_fail: executorParams.chargeActionFeesOnFail,
     full_body_in_bounced: executorParams.fullBodyInBounced,